@@ -0,0 +1,45 @@
+use crate::chess::{Game, Move};
+use crate::notation;
+
+/// Known good moves in UCI notation, keyed by the Zobrist hash of the position they're played from.
+/// The first move of each entry is the main line.
+const BOOK: &[(u64, &[&str])] = &[
+    // start position
+    (0xC7C5B1B91F0B8796, &["e2e4", "d2d4", "g1f3", "c2c4"]),
+    // e2e4
+    (0x58EE45134C03AF3D, &["e7e5", "c7c5", "e7e6", "c7c6"]),
+    // e2e4 e7e5
+    (0x2B9B6DAC8D45C8C7, &["g1f3"]),
+    // e2e4 e7e5 g1f3
+    (0x8FBEAF40E80DB1A8, &["b8c6"]),
+    // e2e4 e7e5 g1f3 b8c6
+    (0x61CB31990BB367E9, &["f1b5", "f1c4"]),
+    // e2e4 c7c5
+    (0x983CC0A5D95849A9, &["g1f3"]),
+    // d2d4
+    (0x27D17115B7D87339, &["d7d5", "g8f6"]),
+    // d2d4 d7d5
+    (0x839C0FE8F23848DD, &["c2c4"]),
+    // d2d4 g8f6
+    (0xB53746258C3A4F52, &["c2c4"]),
+    // g1f3
+    (0x63E073557A43FEF9, &["d7d5", "g8f6"]),
+    // c2c4
+    (0x1A8DBDDA4C6055CB, &["e7e5", "g8f6"]),
+];
+
+impl Game {
+    /// All book moves for the current position, main line first.
+    pub fn book_moves(&self) -> Vec<Move> {
+        let hash = self.zobrist_hash();
+        BOOK.iter()
+            .filter(|&&(key, _)| key == hash)
+            .flat_map(|&(_, moves)| moves.iter().filter_map(|uci| notation::parse_uci(self, uci)))
+            .collect()
+    }
+
+    /// The main line book move for the current position, if it's in the book.
+    pub fn book_move(&self) -> Option<Move> {
+        self.book_moves().first().copied()
+    }
+}
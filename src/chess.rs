@@ -1,6 +1,12 @@
 use std::collections::HashSet;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+use crate::fen::{self, FenError};
+use crate::notation;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PieceType {
     Pawn,
     Bishop,
@@ -10,20 +16,46 @@ pub enum PieceType {
     King
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+impl PieceType {
+    /// Standard value in centipawns. The king can't be traded, so it gets a sentinel worth more than
+    /// all other material together.
+    pub const fn value(&self) -> i32 {
+        match self {
+            PieceType::Pawn => 100,
+            PieceType::Knight => 320,
+            PieceType::Bishop => 330,
+            PieceType::Rook => 500,
+            PieceType::Queen => 900,
+            PieceType::King => 20_000
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Color {
     White,
     Black
 }
 
-#[derive(Clone, Copy, Debug)]
+impl Color {
+    pub fn opponent(&self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Piece {
     piece_type: PieceType,
     piece_color: Color,
 }
 
 impl Piece {
-    const fn new(piece_type: PieceType, piece_color: Color) -> Self {
+    pub const fn new(piece_type: PieceType, piece_color: Color) -> Self {
         Piece {
             piece_type,
             piece_color,
@@ -32,6 +64,36 @@ impl Piece {
 }
 
 impl Piece {
+    /// The piece for a FEN letter, uppercase for White and lowercase for Black, e.g. 'N' or 'q'.
+    pub fn from_fen_char(c: char) -> Option<Piece> {
+        let piece_color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+        let piece_type = match c.to_ascii_lowercase() {
+            'p' => PieceType::Pawn,
+            'n' => PieceType::Knight,
+            'b' => PieceType::Bishop,
+            'r' => PieceType::Rook,
+            'q' => PieceType::Queen,
+            'k' => PieceType::King,
+            _ => return None
+        };
+        Some(Piece::new(piece_type, piece_color))
+    }
+
+    pub fn to_fen_char(&self) -> char {
+        let c = match self.piece_type {
+            PieceType::Pawn => 'p',
+            PieceType::Knight => 'n',
+            PieceType::Bishop => 'b',
+            PieceType::Rook => 'r',
+            PieceType::Queen => 'q',
+            PieceType::King => 'k'
+        };
+        match self.piece_color {
+            Color::White => c.to_ascii_uppercase(),
+            Color::Black => c
+        }
+    }
+
     pub fn piece_color(&self) -> Color {
         self.piece_color
     }
@@ -42,13 +104,239 @@ impl Piece {
 
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    from_x: usize,
+    from_y: usize,
+    to_x: usize,
+    to_y: usize,
+    promotion: Option<PieceType>
+}
+
+impl Move {
+    /// A move without a promotion piece named. A pawn moved to the last rank this way becomes a queen.
+    pub const fn new(from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> Self {
+        Move {
+            from_x,
+            from_y,
+            to_x,
+            to_y,
+            promotion: None
+        }
+    }
+
+    /// A pawn move to the last rank that promotes to `piece_type`.
+    pub const fn new_promotion(from_x: usize, from_y: usize, to_x: usize, to_y: usize, piece_type: PieceType) -> Self {
+        Move {
+            from_x,
+            from_y,
+            to_x,
+            to_y,
+            promotion: Some(piece_type)
+        }
+    }
+
+    pub fn promotion(&self) -> Option<PieceType> {
+        self.promotion
+    }
+
+    /// Whether this is a pawn move to the last rank, where the player picks what to promote to.
+    pub fn is_promotion(&self) -> bool {
+        self.promotion.is_some()
+    }
+
+    pub fn from(&self) -> (usize, usize) {
+        (self.from_x, self.from_y)
+    }
+
+    pub fn to(&self) -> (usize, usize) {
+        (self.to_x, self.to_y)
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Board {
-    tiles: [[Option<Piece>; 8]; 8]
+    tiles: [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE]
+}
+
+/// Why try_make_move_checked turned down a move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    /// There's no piece on the square moved from.
+    EmptySquare,
+    /// The piece belongs to the side not to move.
+    WrongColor,
+    /// The piece can't move that way, or the square moved to holds a piece that can't be taken.
+    NotPseudoLegal,
+    /// The move would leave the mover's own king attacked.
+    LeavesKingInCheck
+}
+
+/// Why a game ended in a draw.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DrawReason {
+    Stalemate,
+    Agreement,
+    FiftyMoveRule,
+    ThreefoldRepetition
+}
+
+/// How a finished game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw(DrawReason)
+}
+
+/// Which sides may still castle. A right is lost for good once the king or the rook involved moves
+/// or is captured. Castling itself isn't a move yet; the rights are kept so they survive a FEN round trip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CastlingRights {
+    white_kingside: bool,
+    white_queenside: bool,
+    black_kingside: bool,
+    black_queenside: bool
+}
+
+impl CastlingRights {
+    pub const ALL: CastlingRights = CastlingRights::new(true, true, true, true);
+    pub const NONE: CastlingRights = CastlingRights::new(false, false, false, false);
+
+    pub const fn new(white_kingside: bool, white_queenside: bool, black_kingside: bool, black_queenside: bool) -> Self {
+        CastlingRights {
+            white_kingside,
+            white_queenside,
+            black_kingside,
+            black_queenside
+        }
+    }
+
+    pub fn kingside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_kingside,
+            Color::Black => self.black_kingside
+        }
+    }
+
+    pub fn queenside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_queenside,
+            Color::Black => self.black_queenside
+        }
+    }
+
+    /// The rights that a position could have, with kings and rooks still on their starting squares.
+    /// `tiles` has rank 1 at y = 0.
+    fn from_placement(tiles: &[[Option<Piece>; BOARD_SIZE]; BOARD_SIZE]) -> Self {
+        let on = |x: usize, y: usize, piece: Piece| tiles[y][x] == Some(piece);
+        let white_king = on(4, 0, KING_WHITE);
+        let black_king = on(4, 7, KING_BLACK);
+        CastlingRights::new(
+            white_king && on(7, 0, ROOK_WHITE),
+            white_king && on(0, 0, ROOK_WHITE),
+            black_king && on(7, 7, ROOK_BLACK),
+            black_king && on(0, 7, ROOK_BLACK)
+        )
+    }
+
+    /// The same rights with White's and Black's swapped.
+    pub fn mirrored(&self) -> CastlingRights {
+        CastlingRights::new(self.black_kingside, self.black_queenside, self.white_kingside, self.white_queenside)
+    }
+
+    /// Drops the rights that depend on a piece standing on (x, rank), for a move from or to there.
+    fn touch(&mut self, x: usize, rank: usize) {
+        match (x, rank) {
+            (4, 0) => { self.white_kingside = false; self.white_queenside = false; },
+            (7, 0) => self.white_kingside = false,
+            (0, 0) => self.white_queenside = false,
+            (4, 7) => { self.black_kingside = false; self.black_queenside = false; },
+            (7, 7) => self.black_kingside = false,
+            (0, 7) => self.black_queenside = false,
+            _ => ()
+        }
+    }
 }
 
+#[derive(PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Game {
     board: Board,
-    player_to_move: Color
+    player_to_move: Color,
+    history: Vec<MoveInfo>,
+    // moves taken back with undo_move, most recent last, until a new move is made
+    redo: Vec<MoveInfo>,
+    fullmove_number: u32,
+    ply: usize,
+    castling: CastlingRights,
+    halfmove_clock: u32,
+    // the side with a draw offer standing, until the other side accepts it or moves
+    draw_offer: Option<Color>,
+    // a result the players settled on themselves rather than one that follows from the position
+    declared_result: Option<GameResult>,
+    // whether a move reaching the fifty move rule or a threefold repetition ends the game
+    auto_draw: bool,
+    // the length of the history when each null move still on the board was made
+    null_moves: Vec<usize>,
+    // whether get_all_legal_moves keeps its result until the board changes
+    cache_legal_moves: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    legal_move_cache: LegalMoveCache
+}
+
+/// The legal moves of the current position once they've been generated, dropped by any change to
+/// the board. Not part of the game itself, so it never makes two games compare unequal.
+#[derive(Default)]
+struct LegalMoveCache(Option<Vec<Move>>);
+
+impl PartialEq for LegalMoveCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// A game as the position it started from and the moves played since, which keeps its history
+/// where a FEN of the current position wouldn't. Made by Game::save_state.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameState {
+    start_fen: String,
+    moves: Vec<Move>
+}
+
+impl GameState {
+    pub fn start_fen(&self) -> &str {
+        &self.start_fen
+    }
+
+    /// The moves in the order they were played, each as seen by its mover.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+}
+
+/// Why Game::load_state couldn't set up a game.
+#[derive(Debug, PartialEq)]
+pub enum StateError {
+    Fen(FenError),
+    /// The move at this index in the move list is illegal where it's played.
+    IllegalMove(usize, MoveError)
+}
+
+/// A collision-free key for a position, usable in hash maps. Two games have equal keys
+/// exactly when `same_position` holds between them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PositionKey {
+    tiles: [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE],
+    player_to_move: Color,
+    castling: CastlingRights,
+    en_passant: Option<(usize, usize)>
 }
 
 const PAWN_WHITE: Piece = Piece::new(PieceType::Pawn, Color::White);
@@ -64,14 +352,27 @@ const QUEEN_BLACK: Piece = Piece::new(PieceType::Queen, Color::Black);
 const KING_WHITE: Piece = Piece::new(PieceType::King, Color::White);
 const KING_BLACK: Piece = Piece::new(PieceType::King, Color::Black);
 
-pub const BOARD_DEFAULT_SETUP: [[Option<Piece>; 8]; 8] = [
+/// Squares along each side of the board.
+pub const BOARD_SIZE: usize = 8;
+
+/// Every piece type in the order they're declared, so `piece_type as usize` indexes into it.
+pub const ALL_PIECE_TYPES: [PieceType; 6] = [PieceType::Pawn, PieceType::Bishop, PieceType::Knight, PieceType::Rook, PieceType::Queen, PieceType::King];
+
+pub const ALL_COLORS: [Color; 2] = [Color::White, Color::Black];
+
+const PROMOTION_PIECE_TYPES: [PieceType; 4] = [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight];
+
+// a queen in the middle of an empty board has the most places to go
+const MAX_PSEUDO_MOVES: usize = 27;
+
+pub const BOARD_DEFAULT_SETUP: [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE] = [
     [Some(ROOK_WHITE), Some(KNIGHT_WHITE), Some(BISHOP_WHITE), Some(QUEEN_WHITE), Some(KING_WHITE), Some(BISHOP_WHITE), Some(KNIGHT_WHITE), Some(ROOK_WHITE)],
-    [Some(PAWN_WHITE); 8],
-    [None; 8],
-    [None; 8],
-    [None; 8],
-    [None; 8],
-    [Some(PAWN_BLACK); 8],
+    [Some(PAWN_WHITE); BOARD_SIZE],
+    [None; BOARD_SIZE],
+    [None; BOARD_SIZE],
+    [None; BOARD_SIZE],
+    [None; BOARD_SIZE],
+    [Some(PAWN_BLACK); BOARD_SIZE],
     [Some(ROOK_BLACK), Some(KNIGHT_BLACK), Some(BISHOP_BLACK), Some(QUEEN_BLACK), Some(KING_BLACK), Some(BISHOP_BLACK), Some(KNIGHT_BLACK), Some(ROOK_BLACK)],
 ];
 
@@ -81,13 +382,81 @@ struct RaycastInfo {
     path: HashSet<(usize, usize)>
 }
 
-struct MoveInfo {
+/// The squares along a line from a square, as returned by Game::attack_ray.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttackRay {
+    squares: Vec<(usize, usize)>,
+    blocker: Option<((usize, usize), Piece)>
+}
+
+impl AttackRay {
+    /// The empty squares passed, nearest first.
+    pub fn squares(&self) -> &[(usize, usize)] {
+        &self.squares
+    }
+
+    /// The square the line runs into and the piece on it, or None if it reaches the edge of the board.
+    pub fn blocker(&self) -> Option<((usize, usize), Piece)> {
+        self.blocker
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoveInfo {
     moved: Piece,
     captured: Option<Piece>,
+    promotion: Option<PieceType>,
     from_x: usize,
     from_y: usize,
     to_x: usize,
-    to_y: usize
+    to_y: usize,
+    // the captured pawn stood beside the mover rather than on the destination
+    en_passant: bool,
+    // state from before the move, for restore_move to put back
+    castling: CastlingRights,
+    halfmove_clock: u32
+}
+
+impl MoveInfo {
+    pub fn moved(&self) -> Piece {
+        self.moved
+    }
+
+    pub fn captured(&self) -> Option<Piece> {
+        self.captured
+    }
+
+    pub fn promotion(&self) -> Option<PieceType> {
+        self.promotion
+    }
+
+    /// The squares are as seen by the mover, since the board is flipped every turn.
+    pub fn from(&self) -> (usize, usize) {
+        (self.from_x, self.from_y)
+    }
+
+    pub fn to(&self) -> (usize, usize) {
+        (self.to_x, self.to_y)
+    }
+
+    fn as_move(&self) -> Move {
+        Move {
+            from_x: self.from_x,
+            from_y: self.from_y,
+            to_x: self.to_x,
+            to_y: self.to_y,
+            promotion: self.promotion
+        }
+    }
+}
+
+/// The rank pawns of `color` start on and may advance two squares from, 0 being rank 1.
+fn pawn_home_rank(color: Color) -> usize {
+    match color {
+        Color::White => 1,
+        Color::Black => 6
+    }
 }
 
 impl Game {
@@ -97,7 +466,7 @@ impl Game {
     }
 
     fn is_bounded(&self, x: isize, y: isize) -> bool {
-        x >= 0 && x < 8 && y >= 0 && y < 8
+        (0..BOARD_SIZE as isize).contains(&x) && (0..BOARD_SIZE as isize).contains(&y)
     }
 
     fn cast_ray(&self, x: usize, y: usize, dx: isize, dy: isize, steps: Option<usize>) -> RaycastInfo {
@@ -160,7 +529,7 @@ impl Game {
         true
     }
 
-    fn get_pseudo_captures_pawn(&self, x: usize, y: usize, captures: &mut HashSet<(usize, usize)>) -> () {
+    fn get_pseudo_captures_pawn(&self, x: usize, y: usize, captures: &mut HashSet<(usize, usize)>) {
         let raycast = self.cast_ray(x, y, 1, 1, Some(1));
         if raycast.is_hit {
             if let Some(point) = raycast.point {
@@ -173,16 +542,25 @@ impl Game {
                 captures.insert(point);
             }
         }
+        // only the side to move can take en passant, and only right after the double push
+        if self.get_piece(x, y).is_some_and(|piece| piece.piece_color == self.player_to_move) {
+            if let Some((ex, ey)) = self.en_passant_square() {
+                if ey == y + 1 && ex.abs_diff(x) == 1 {
+                    captures.insert((ex, ey));
+                }
+            }
+        }
     }
 
-    fn get_pseudo_moves_pawn(&self, x: usize, y: usize, moves: &mut HashSet<(usize, usize)>) -> () {
+    fn get_pseudo_moves_pawn(&self, x: usize, y: usize, moves: &mut HashSet<(usize, usize)>) {
         let raycast = self.cast_ray(x, y, 0, 1, Some(1));
         if !raycast.is_hit {
             if let Some(point) = raycast.point {
                 moves.insert(point);
             }
         }
-        if y == 1 {
+        // the ray stops at the first piece, so the push is also blocked by one right in front
+        if self.get_piece(x, y).is_some_and(|pawn| self.oriented(y) == pawn_home_rank(pawn.piece_color)) {
             let raycast = self.cast_ray(x, y, 0, 1, Some(2));
             if !raycast.is_hit {
                 if let Some(point) = raycast.point {
@@ -192,7 +570,7 @@ impl Game {
         }
     }
 
-    fn get_pseudo_moves_bishop(&self, x: usize, y: usize, moves: &mut HashSet<(usize, usize)>) -> () {
+    fn get_pseudo_moves_bishop(&self, x: usize, y: usize, moves: &mut HashSet<(usize, usize)>) {
         for dx in [-1, 1] {
             for dy in [-1, 1] {
                 let raycast = self.cast_ray(x, y, dx, dy, None);
@@ -201,7 +579,7 @@ impl Game {
         }
     }
     
-    fn get_pseudo_captures_bishop(&self, x: usize, y: usize, captures: &mut HashSet<(usize, usize)>) -> () {
+    fn get_pseudo_captures_bishop(&self, x: usize, y: usize, captures: &mut HashSet<(usize, usize)>) {
         for dx in [-1, 1] {
             for dy in [-1, 1] {
                 let raycast = self.cast_ray(x, y, dx, dy, None);
@@ -214,7 +592,7 @@ impl Game {
         }
     }
 
-    fn get_pseudo_moves_knight(&self, x: usize, y: usize, moves: &mut HashSet<(usize, usize)>) -> () {
+    fn get_pseudo_moves_knight(&self, x: usize, y: usize, moves: &mut HashSet<(usize, usize)>) {
         for (dx, dy) in [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)] {
             let raycast = self.cast_ray(x, y, dx, dy, Some(1));
             if !raycast.is_hit {
@@ -225,7 +603,7 @@ impl Game {
         }
     }
 
-    fn get_pseudo_captures_knight(&self, x: usize, y: usize, moves: &mut HashSet<(usize, usize)>) -> () {
+    fn get_pseudo_captures_knight(&self, x: usize, y: usize, moves: &mut HashSet<(usize, usize)>) {
         for (dx, dy) in [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)] {
             let raycast = self.cast_ray(x, y, dx, dy, Some(1));
             if raycast.is_hit {
@@ -236,7 +614,7 @@ impl Game {
         }
     }
     
-    fn get_pseudo_moves_rook(&self, x: usize, y: usize, moves: &mut HashSet<(usize, usize)>) -> () {
+    fn get_pseudo_moves_rook(&self, x: usize, y: usize, moves: &mut HashSet<(usize, usize)>) {
         for d in [-1, 1] {
             let raycast = self.cast_ray(x, y, 0, d, None);
             moves.extend(raycast.path);
@@ -245,7 +623,7 @@ impl Game {
         }
     }
 
-    fn get_pseudo_captures_rook(&self, x: usize, y: usize, captures: &mut HashSet<(usize, usize)>) -> () {
+    fn get_pseudo_captures_rook(&self, x: usize, y: usize, captures: &mut HashSet<(usize, usize)>) {
         for d in [-1, 1] {
             let raycast = self.cast_ray(x, y, 0, d, None);
             if raycast.is_hit {
@@ -263,17 +641,17 @@ impl Game {
     }
 
     // :D
-    fn get_pseudo_moves_queen(&self, x: usize, y: usize, captures: &mut HashSet<(usize, usize)>) -> () {
+    fn get_pseudo_moves_queen(&self, x: usize, y: usize, captures: &mut HashSet<(usize, usize)>) {
         self.get_pseudo_moves_bishop(x, y, captures);
         self.get_pseudo_moves_rook(x, y, captures);
     }
 
-    fn get_pseudo_captures_queen(&self, x: usize, y: usize, captures: &mut HashSet<(usize, usize)>) -> () {
+    fn get_pseudo_captures_queen(&self, x: usize, y: usize, captures: &mut HashSet<(usize, usize)>) {
         self.get_pseudo_captures_bishop(x, y, captures);
         self.get_pseudo_captures_rook(x, y, captures);
     }
 
-    fn get_pseudo_moves_king(&self, x: usize, y: usize, captures: &mut HashSet<(usize, usize)>) -> () {
+    fn get_pseudo_moves_king(&self, x: usize, y: usize, captures: &mut HashSet<(usize, usize)>) {
         for dx in [-1, 0, 1] {
             for dy in [-1, 0, 1] {
                 let raycast = self.cast_ray(x, y, dx, dy, Some(1));
@@ -286,7 +664,7 @@ impl Game {
         }
     }
 
-    fn get_pseudo_captures_king(&self, x: usize, y: usize, captures: &mut HashSet<(usize, usize)>) -> () {
+    fn get_pseudo_captures_king(&self, x: usize, y: usize, captures: &mut HashSet<(usize, usize)>) {
         for dx in [-1, 0, 1] {
             for dy in [-1, 0, 1] {
                 let raycast = self.cast_ray(x, y, dx, dy, Some(1));
@@ -315,7 +693,7 @@ impl Game {
     }
     
     fn is_pseudo_legal(&self, from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> bool {
-        if let Some(_) = self.get_piece(from_x, from_y) {
+        if self.get_piece(from_x, from_y).is_some() {
             let pseudo_moves = self.get_pseudo_moves(from_x, from_y);
             if pseudo_moves.contains(&(to_x, to_y)) {
                 return true;
@@ -324,6 +702,73 @@ impl Game {
         false
     }
 
+    /// The same squares as get_pseudo_moves, handed to `visit` one at a time instead of collected.
+    /// Stops as soon as `visit` returns false, and returns whether every square was visited.
+    fn for_each_pseudo_move(&self, x: usize, y: usize, visit: &mut impl FnMut(usize, usize) -> bool) -> bool {
+        let Some(piece) = self.get_piece(x, y) else {
+            return true;
+        };
+        match piece.piece_type {
+            PieceType::Pawn => {
+                let pushes = if self.oriented(y) == pawn_home_rank(piece.piece_color) { 2 } else { 1 };
+                for to_y in (y + 1..BOARD_SIZE).take(pushes) {
+                    if !self.is_empty(x, to_y) {
+                        break;
+                    }
+                    if !visit(x, to_y) {
+                        return false;
+                    }
+                }
+                let en_passant = self.en_passant_square().filter(|_| piece.piece_color == self.player_to_move);
+                for dx in [-1, 1] {
+                    let (to_x, to_y) = (x as isize + dx, y as isize + 1);
+                    if self.is_bounded(to_x, to_y) {
+                        let to = (to_x as usize, to_y as usize);
+                        if (!self.is_empty(to.0, to.1) || Some(to) == en_passant) && !visit(to.0, to.1) {
+                            return false;
+                        }
+                    }
+                }
+                true
+            },
+            PieceType::Knight => [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)]
+                .into_iter()
+                .all(|(dx, dy)| self.walk_ray(x, y, dx, dy, Some(1), visit)),
+            PieceType::King => [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)]
+                .into_iter()
+                .all(|(dx, dy)| self.walk_ray(x, y, dx, dy, Some(1), visit)),
+            PieceType::Bishop => [(1, 1), (-1, 1), (-1, -1), (1, -1)]
+                .into_iter()
+                .all(|(dx, dy)| self.walk_ray(x, y, dx, dy, None, visit)),
+            PieceType::Rook => [(1, 0), (0, 1), (-1, 0), (0, -1)]
+                .into_iter()
+                .all(|(dx, dy)| self.walk_ray(x, y, dx, dy, None, visit)),
+            PieceType::Queen => [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)]
+                .into_iter()
+                .all(|(dx, dy)| self.walk_ray(x, y, dx, dy, None, visit)),
+        }
+    }
+
+    /// Like cast_ray, but visits the empty squares and then the piece it runs into as it goes,
+    /// without building the path.
+    fn walk_ray(&self, x: usize, y: usize, dx: isize, dy: isize, steps: Option<usize>, visit: &mut impl FnMut(usize, usize) -> bool) -> bool {
+        let (mut rx, mut ry) = (x as isize, y as isize);
+        for _ in 0..steps.unwrap_or(BOARD_SIZE) {
+            rx += dx;
+            ry += dy;
+            if !self.is_bounded(rx, ry) {
+                break;
+            }
+            if !visit(rx as usize, ry as usize) {
+                return false;
+            }
+            if !self.is_empty(rx as usize, ry as usize) {
+                break;
+            }
+        }
+        true
+    }
+
     fn get_pseudo_captures(&self, x: usize, y: usize) -> HashSet<(usize, usize)> {
         let mut captures: HashSet<(usize, usize)> = HashSet::new();
         if let Some(piece) = self.get_piece(x, y) {
@@ -340,104 +785,316 @@ impl Game {
     }
 
     fn find_king(&self) -> Option<(usize, usize)> {
-        for y in 0..8 {
-            for x in 0..8 {
-                if let Some(piece) = self.get_piece(x, y) {
-                    if piece.piece_type == PieceType::King {
-                        if piece.piece_color == self.player_to_move {
-                            return Some((x, y));
-                        }
-                    }
-                }
-            }
-        }
-        None
+        let king = Piece::new(PieceType::King, self.player_to_move);
+        (0..BOARD_SIZE).flat_map(|y| (0..BOARD_SIZE).map(move |x| (x, y))).find(|&(x, y)| self.get_piece(x, y) == Some(king))
     }
 
-    fn flip_board(&mut self) -> () {
+    fn flip_board(&mut self) {
+        self.legal_move_cache.0 = None;
         self.board.tiles.reverse();
     }
 
-    fn in_check(&mut self) -> bool {
-        self.flip_board();
-        if let Some(king) = self.find_king() {
-            for y in 0..8 {
-                for x in 0..8 {
-                    if let Some(piece) = self.get_piece(x, y) {
-                        if piece.piece_color != self.player_to_move {
-                            let captures = self.get_pseudo_captures(x, y);
-                            if captures.contains(&king) {
-                                self.flip_board();
-                                return true;
-                            }
-                        }
+    /// Whether the king of the side to move is attacked.
+    pub fn in_check(&self) -> bool {
+        let (king_x, king_y) = match self.find_king() {
+            Some(king) => king,
+            None => return false
+        };
+        for y in 0..BOARD_SIZE {
+            for x in 0..BOARD_SIZE {
+                if let Some(piece) = self.get_piece(x, y) {
+                    if piece.piece_color == self.player_to_move {
+                        continue;
+                    }
+                    // the opponent's pawns capture down the board, the other way from the mover's
+                    let attacks = match piece.piece_type {
+                        PieceType::Pawn => y == king_y + 1 && x.abs_diff(king_x) == 1,
+                        _ => !self.for_each_pseudo_move(x, y, &mut |to_x, to_y| (to_x, to_y) != (king_x, king_y))
+                    };
+                    if attacks {
+                        return true;
                     }
                 }
             }
         }
-        self.flip_board();
         false
     }
 
-    fn swap_turn(&mut self) -> () {
+    /// Converts between board rows and ranks (0 = rank 1). The board is flipped so the side
+    /// to move is always at row 0, which makes this conversion its own inverse.
+    pub(crate) fn oriented(&self, y: usize) -> usize {
+        match self.player_to_move {
+            Color::White => y,
+            Color::Black => BOARD_SIZE - 1 - y
+        }
+    }
+
+    /// A copy of the position with `color` to move, the board flipped over if that's the other side.
+    fn seen_by(&self, color: Color) -> Game {
+        let mut tiles = self.board.tiles;
+        if color != self.player_to_move {
+            tiles.reverse();
+        }
+        Game {
+            board: Board::new(tiles),
+            player_to_move: color,
+            history: Vec::new(),
+            redo: Vec::new(),
+            fullmove_number: self.fullmove_number,
+            ply: self.ply,
+            castling: self.castling,
+            halfmove_clock: self.halfmove_clock,
+            draw_offer: None,
+            declared_result: None,
+            auto_draw: false,
+            null_moves: Vec::new(),
+            cache_legal_moves: false,
+            legal_move_cache: LegalMoveCache::default()
+        }
+    }
+
+    fn swap_turn(&mut self) {
         self.player_to_move = match self.player_to_move {
             Color::White => Color::Black,
             Color::Black => Color::White
         };
     }
 
-    fn make_move(&mut self, from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> MoveInfo {
+    fn make_move(&mut self, from_x: usize, from_y: usize, to_x: usize, to_y: usize, promotion: Option<PieceType>) -> MoveInfo {
+        self.legal_move_cache.0 = None;
         let moved = self.board.tiles[from_y][from_x].expect("shouldn't be moving empty");
-        let captured = self.board.tiles[to_y][to_x];
+        let en_passant = moved.piece_type == PieceType::Pawn && from_x != to_x && self.board.tiles[to_y][to_x].is_none();
+        let captured = if en_passant { self.board.tiles[from_y][to_x].take() } else { self.board.tiles[to_y][to_x] };
+        let promotion = if moved.piece_type == PieceType::Pawn && to_y == BOARD_SIZE - 1 { Some(promotion.unwrap_or(PieceType::Queen)) } else { None };
         let info = MoveInfo {
             moved,
             captured,
+            promotion,
             from_x,
             from_y,
             to_x,
-            to_y
+            to_y,
+            en_passant,
+            castling: self.castling,
+            halfmove_clock: self.halfmove_clock
         };
+        self.castling.touch(from_x, self.oriented(from_y));
+        self.castling.touch(to_x, self.oriented(to_y));
+        if moved.piece_type == PieceType::Pawn || captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
         self.board.tiles[to_y][to_x] = self.board.tiles[from_y][from_x];
         self.board.tiles[from_y][from_x] = None;
+        if let Some(piece_type) = promotion {
+            self.board.tiles[to_y][to_x] = Some(Piece::new(piece_type, moved.piece_color));
+        }
         info
     }
 
-    fn unmake_move(&mut self, move_info: MoveInfo) -> () {
-        self.board.tiles[move_info.to_y][move_info.to_x] = move_info.captured;
+    fn restore_move(&mut self, move_info: MoveInfo) {
+        self.legal_move_cache.0 = None;
+        self.castling = move_info.castling;
+        self.halfmove_clock = move_info.halfmove_clock;
+        if move_info.en_passant {
+            self.board.tiles[move_info.to_y][move_info.to_x] = None;
+            self.board.tiles[move_info.from_y][move_info.to_x] = move_info.captured;
+        } else {
+            self.board.tiles[move_info.to_y][move_info.to_x] = move_info.captured;
+        }
         self.board.tiles[move_info.from_y][move_info.from_x] = Some(move_info.moved);
     }
 
+    fn end_turn(&mut self) {
+        if self.player_to_move == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.ply += 1;
+        self.swap_turn();
+        self.flip_board();
+    }
+
+    fn undo_turn(&mut self) {
+        self.flip_board();
+        self.swap_turn();
+        self.ply -= 1;
+        if self.player_to_move == Color::Black {
+            self.fullmove_number -= 1;
+        }
+    }
+
+    fn play_move(&mut self, m: Move) -> MoveInfo {
+        let move_info = self.make_move(m.from_x, m.from_y, m.to_x, m.to_y, m.promotion);
+        self.end_turn();
+        move_info
+    }
+
+    fn unplay_move(&mut self, move_info: MoveInfo) {
+        self.undo_turn();
+        self.restore_move(move_info);
+    }
+
     fn is_legal_move(&mut self, from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> bool {
-        if let Some(piece) = self.get_piece(from_x, from_y) {
-            if piece.piece_color == self.player_to_move {
-                if self.is_pseudo_legal(from_x, from_y, to_x, to_y) {
-                    if let Some(_) = self.get_piece(to_x, to_y) {
-                        if !self.can_be_here(to_x, to_y) {
-                            return false;
-                        }
-                    }
-                    let move_info = self.make_move(from_x, from_y, to_x, to_y);
-                    let checked: bool = self.in_check();
-                    self.unmake_move(move_info);
-                    if checked {
-                        return false;
-                    }
-                    return true;
-                }
+        self.check_move(from_x, from_y, to_x, to_y).is_ok()
+    }
+
+    fn check_move(&mut self, from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> Result<(), MoveError> {
+        self.check_pseudo_legal(from_x, from_y, to_x, to_y)?;
+        // what a pawn promotes to can't matter to its own king
+        let move_info = self.make_move(from_x, from_y, to_x, to_y, None);
+        let checked: bool = self.in_check();
+        self.restore_move(move_info);
+        if checked {
+            return Err(MoveError::LeavesKingInCheck);
+        }
+        Ok(())
+    }
+
+    /// Everything check_move looks at except what the move leaves the mover's king open to.
+    fn check_pseudo_legal(&self, from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> Result<(), MoveError> {
+        let piece = self.get_piece(from_x, from_y).ok_or(MoveError::EmptySquare)?;
+        if piece.piece_color != self.player_to_move {
+            return Err(MoveError::WrongColor);
+        }
+        if !self.is_pseudo_legal(from_x, from_y, to_x, to_y) {
+            return Err(MoveError::NotPseudoLegal);
+        }
+        if self.get_piece(to_x, to_y).is_some() && !self.can_be_here(to_x, to_y) {
+            return Err(MoveError::NotPseudoLegal);
+        }
+        Ok(())
+    }
+
+    /// That a promotion piece is only named for a pawn reaching the last rank.
+    fn check_promotion(&self, m: Move) -> Result<(), MoveError> {
+        if let Some(piece_type) = m.promotion {
+            let is_promotion = m.to_y == BOARD_SIZE - 1 && self.get_piece(m.from_x, m.from_y).is_some_and(|piece| piece.piece_type == PieceType::Pawn);
+            if !is_promotion || !PROMOTION_PIECE_TYPES.contains(&piece_type) {
+                return Err(MoveError::NotPseudoLegal);
+            }
+        }
+        Ok(())
+    }
+
+    fn check_full_move(&mut self, m: Move) -> Result<(), MoveError> {
+        self.check_promotion(m)?;
+        self.check_move(m.from_x, m.from_y, m.to_x, m.to_y)
+    }
+
+    // a pawn reaching the last rank gets one move for each piece it can promote to
+    fn push_moves(&self, moves: &mut Vec<Move>, x: usize, y: usize, destinations: Vec<(usize, usize)>) {
+        let is_pawn = self.get_piece(x, y).is_some_and(|piece| piece.piece_type == PieceType::Pawn);
+        for (to_x, to_y) in destinations {
+            if is_pawn && to_y == BOARD_SIZE - 1 {
+                moves.extend(PROMOTION_PIECE_TYPES.map(|piece_type| Move::new_promotion(x, y, to_x, to_y, piece_type)));
+            } else {
+                moves.push(Move::new(x, y, to_x, to_y));
             }
         }
-        false
     }
 }
 
 impl Game {
+    /// Sets up a game with White to move. Castling rights are given to kings and rooks still
+    /// on their starting squares.
     pub fn new(board: Board) -> Self {
         Game {
+            castling: CastlingRights::from_placement(&board.tiles),
             board,
-            player_to_move: Color::White
+            player_to_move: Color::White,
+            history: Vec::new(),
+            redo: Vec::new(),
+            fullmove_number: 1,
+            ply: 0,
+            halfmove_clock: 0,
+            draw_offer: None,
+            declared_result: None,
+            auto_draw: false,
+            null_moves: Vec::new(),
+            cache_legal_moves: false,
+            legal_move_cache: LegalMoveCache::default()
         }
     }
 
+    /// Sets up a position with `player_to_move` to play. The board is given with rank 1 at y = 0
+    /// whichever side is to move and is flipped here when Black is.
+    pub(crate) fn from_position(board: Board, player_to_move: Color, castling: CastlingRights, halfmove_clock: u32, fullmove_number: u32) -> Self {
+        let mut game = Game::new(board);
+        game.castling = castling;
+        game.halfmove_clock = halfmove_clock;
+        game.fullmove_number = fullmove_number;
+        game.ply = 2 * (fullmove_number as usize - 1);
+        if player_to_move == Color::Black {
+            game.ply += 1;
+            game.swap_turn();
+            game.flip_board();
+        }
+        game
+    }
+
+    /// The same position with the colors reversed: the board turned over top to bottom, every piece
+    /// changing color, and the other side to move with the other side's castling rights. Evaluations
+    /// of the two should be opposite. The history isn't carried over.
+    pub fn mirrored(&self) -> Game {
+        // the board is stored from the mover's side, so flipping it and swapping the mover cancel out
+        let mut tiles = self.board.tiles;
+        for piece in tiles.iter_mut().flatten().flatten() {
+            piece.piece_color = piece.piece_color.opponent();
+        }
+        let player_to_move = self.player_to_move.opponent();
+        Game {
+            board: Board::new(tiles),
+            player_to_move,
+            history: Vec::new(),
+            redo: Vec::new(),
+            fullmove_number: self.fullmove_number,
+            ply: 2 * (self.fullmove_number as usize - 1) + (player_to_move == Color::Black) as usize,
+            castling: self.castling.mirrored(),
+            halfmove_clock: self.halfmove_clock,
+            draw_offer: None,
+            declared_result: None,
+            auto_draw: false,
+            null_moves: Vec::new(),
+            cache_legal_moves: false,
+            legal_move_cache: LegalMoveCache::default()
+        }
+    }
+
+    /// Sets up a game from a FEN string, see fen::parse.
+    pub fn from_fen(fen: &str) -> Result<Game, FenError> {
+        fen::parse(fen)
+    }
+
+    /// Writes the position as FEN, see fen::to_fen.
+    pub fn to_fen(&self) -> String {
+        fen::to_fen(self)
+    }
+
+    /// The starting position and the moves played from it, for a save file that load_state turns back
+    /// into the same game with its history. Moves taken back for redo_move aren't kept, and neither
+    /// are null moves, so save between moves rather than in the middle of a search.
+    pub fn save_state(&self) -> GameState {
+        let mut start = fen::parse(&fen::to_fen(self)).expect("to_fen writes a valid FEN");
+        for &move_info in self.history.iter().rev() {
+            start.unplay_move(move_info);
+        }
+        GameState {
+            start_fen: fen::to_fen(&start),
+            moves: self.history.iter().map(MoveInfo::as_move).collect()
+        }
+    }
+
+    /// Sets up the game saved with save_state, playing its moves from the starting position so
+    /// undo_move and repetitions work as they did before saving.
+    pub fn load_state(state: &GameState) -> Result<Game, StateError> {
+        let mut game = fen::parse(&state.start_fen).map_err(StateError::Fen)?;
+        for (i, &m) in state.moves.iter().enumerate() {
+            game.try_play_move(m).map_err(|error| StateError::IllegalMove(i, error))?;
+        }
+        Ok(game)
+    }
+
     pub fn board(&self) -> &Board {
         &self.board
     }
@@ -446,64 +1103,833 @@ impl Game {
         self.player_to_move
     }
     
+    /// Starts at 1 and goes up after each of Black's moves.
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    /// The number of half-moves played.
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling
+    }
+
+    /// Half-moves since the last capture or pawn move.
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    /// The moves made so far, oldest first.
+    pub fn history(&self) -> &[MoveInfo] {
+        &self.history
+    }
+
+    /// The pieces of `color` captured so far in the order they were taken, worked out from the history
+    /// so it follows undo_move.
+    pub fn captured_pieces(&self, color: Color) -> Vec<Piece> {
+        self.history.iter().filter_map(|move_info| move_info.captured).filter(|piece| piece.piece_color == color).collect()
+    }
+
     pub fn get_piece(&self, x: usize, y: usize) -> Option<Piece> {
         self.board.tiles[y][x]
     }
 
     pub fn get_legal_moves(&mut self, x: usize, y: usize) -> HashSet<(usize, usize)> {
+        if self.cache_legal_moves {
+            return self.get_all_legal_moves().into_iter().filter(|m| m.from() == (x, y)).map(|m| m.to()).collect();
+        }
+        self.legal_destinations(x, y)
+    }
+
+    fn legal_destinations(&mut self, x: usize, y: usize) -> HashSet<(usize, usize)> {
         self.get_pseudo_moves(x, y).into_iter().filter(|&(mx, my)| self.is_legal_move(x, y, mx, my)).collect()
     }
 
-    fn can_make_any_move(&mut self) -> bool {
-        for y in 0..8 {
-            for x in 0..8 {
-                if let Some(_) = self.get_piece(x, y) {
-                    let moves = self.get_legal_moves(x, y);
-                    if !moves.is_empty() {
-                        return true;
+    /// Legal moves from (x, y) that capture an enemy piece.
+    pub fn get_legal_captures(&mut self, x: usize, y: usize) -> HashSet<(usize, usize)> {
+        let is_pawn = self.get_piece(x, y).is_some_and(|piece| piece.piece_type == PieceType::Pawn);
+        let en_passant = self.en_passant_square().filter(|_| is_pawn);
+        self.get_pseudo_captures(x, y)
+            .into_iter()
+            .filter(|&(cx, cy)| (self.get_piece(cx, cy).is_some() || Some((cx, cy)) == en_passant) && self.is_legal_move(x, y, cx, cy))
+            .collect()
+    }
+
+    /// All legal captures for the side to move, in the same order as get_all_legal_moves.
+    pub fn get_all_legal_captures(&mut self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for y in 0..BOARD_SIZE {
+            for x in 0..BOARD_SIZE {
+                let mut targets: Vec<(usize, usize)> = self.get_legal_captures(x, y).into_iter().collect();
+                targets.sort_by_key(|&(to_x, to_y)| (to_y, to_x));
+                self.push_moves(&mut moves, x, y, targets);
+            }
+        }
+        moves
+    }
+
+    /// All legal moves for the side to move, ordered by source square and then destination. A pawn
+    /// reaching the last rank gives four moves, promoting to a queen, rook, bishop and knight in that order.
+    pub fn get_all_legal_moves(&mut self) -> Vec<Move> {
+        if let Some(moves) = &self.legal_move_cache.0 {
+            return moves.clone();
+        }
+        let mut moves = Vec::new();
+        for y in 0..BOARD_SIZE {
+            for x in 0..BOARD_SIZE {
+                moves.extend(self.legal_move_list(x, y));
+            }
+        }
+        if self.cache_legal_moves {
+            self.legal_move_cache.0 = Some(moves.clone());
+        }
+        moves
+    }
+
+    /// Like get_legal_moves, but as Moves ordered by destination, so a promotion shows up as one move
+    /// per piece with Move::is_promotion set. A UI can ask for the piece when a destination has those.
+    pub fn get_legal_move_list(&mut self, x: usize, y: usize) -> Vec<Move> {
+        if self.cache_legal_moves {
+            return self.get_all_legal_moves().into_iter().filter(|m| m.from() == (x, y)).collect();
+        }
+        self.legal_move_list(x, y)
+    }
+
+    fn legal_move_list(&mut self, x: usize, y: usize) -> Vec<Move> {
+        let mut destinations: Vec<(usize, usize)> = self.legal_destinations(x, y).into_iter().collect();
+        destinations.sort_by_key(|&(to_x, to_y)| (to_y, to_x));
+        let mut moves = Vec::new();
+        self.push_moves(&mut moves, x, y, destinations);
+        moves
+    }
+
+    /// The squares of the pieces of the side to move that can legally move to (to_x, to_y), ordered
+    /// by row and then column. The other way round from get_legal_moves, for picking a destination
+    /// first and then the piece to bring there.
+    pub fn all_moves_to(&mut self, to_x: usize, to_y: usize) -> Vec<(usize, usize)> {
+        let mut sources = Vec::new();
+        for y in 0..BOARD_SIZE {
+            for x in 0..BOARD_SIZE {
+                if self.is_legal_move(x, y, to_x, to_y) {
+                    sources.push((x, y));
+                }
+            }
+        }
+        sources
+    }
+
+    /// The moves `color` could make if it were their turn, in this board's coordinates and ordered like
+    /// get_all_legal_moves: by source square, then destination, row before column. "Legal" means legal for that position: for the side not to move these
+    /// are the moves that wouldn't leave their own king in check, which is what they threaten to play.
+    /// The game itself is left untouched.
+    pub fn legal_moves_for(&self, color: Color) -> Vec<Move> {
+        let mut game = self.seen_by(color);
+        let moves = game.get_all_legal_moves();
+        if color == self.player_to_move {
+            return moves;
+        }
+        let flip = |(x, y): (usize, usize)| (x, BOARD_SIZE - 1 - y);
+        let mut moves: Vec<Move> = moves.into_iter().map(|m| {
+            let (from, to) = (flip(m.from()), flip(m.to()));
+            Move { from_x: from.0, from_y: from.1, to_x: to.0, to_y: to.1, promotion: m.promotion }
+        }).collect();
+        // flipping reverses the rows, so sort back into this board's order; the sort is stable,
+        // which keeps a promotion's pieces in order
+        moves.sort_by_key(|m| (m.from_y, m.from_x, m.to_y, m.to_x));
+        moves
+    }
+
+    /// Counts the leaf nodes of the legal move tree `depth` plies deep.
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.get_all_legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        let mut nodes = 0;
+        for m in moves {
+            let move_info = self.play_move(m);
+            nodes += self.perft(depth - 1);
+            self.unplay_move(move_info);
+        }
+        nodes
+    }
+
+    /// Like perft, but split up by the first move to help localize move generation bugs.
+    pub fn perft_divide(&mut self, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+        let mut divide = Vec::new();
+        for m in self.get_all_legal_moves() {
+            let move_info = self.play_move(m);
+            divide.push((m, self.perft(depth - 1)));
+            self.unplay_move(move_info);
+        }
+        divide
+    }
+
+    /// Follows the line from (x, y) in steps of (dx, dy), e.g. (1, 0) along the rank or (1, 1)
+    /// diagonally, up to the first piece or the edge of the board. The piece on (x, y) itself doesn't
+    /// matter, so this is the line a rook, bishop or queen there would control.
+    pub fn attack_ray(&self, x: usize, y: usize, dx: isize, dy: isize) -> AttackRay {
+        if (dx, dy) == (0, 0) {
+            return AttackRay { squares: Vec::new(), blocker: None };
+        }
+        let ray = self.cast_ray(x, y, dx, dy, None);
+        let mut squares: Vec<(usize, usize)> = ray.path.into_iter().collect();
+        squares.sort_by_key(|&(sx, sy)| sx.abs_diff(x).max(sy.abs_diff(y)));
+        let blocker = if ray.is_hit {
+            ray.point.and_then(|(bx, by)| self.get_piece(bx, by).map(|piece| ((bx, by), piece)))
+        } else {
+            None
+        };
+        AttackRay { squares, blocker }
+    }
+
+    /// If the piece on (x, y) is pinned to its own king, the squares it may still stand on without
+    /// exposing the king: the line from the king to the pinning piece, the pinner's square included
+    /// and the king's left out, along with the square the piece is on. None if it isn't pinned.
+    pub fn pin_ray(&self, x: usize, y: usize) -> Option<HashSet<(usize, usize)>> {
+        let piece = self.get_piece(x, y).filter(|piece| piece.piece_type != PieceType::King)?;
+        let &(king_x, king_y) = self.find_pieces(PieceType::King, piece.piece_color).first()?;
+        let (dx, dy) = (x as isize - king_x as isize, y as isize - king_y as isize);
+        if dx != 0 && dy != 0 && dx.abs() != dy.abs() {
+            return None;
+        }
+        let (dx, dy) = (dx.signum(), dy.signum());
+
+        let towards_piece = self.cast_ray(king_x, king_y, dx, dy, None);
+        if towards_piece.point != Some((x, y)) {
+            return None;
+        }
+        let beyond = self.cast_ray(x, y, dx, dy, None);
+        let (pinner_x, pinner_y) = beyond.point.filter(|_| beyond.is_hit)?;
+        let pinner = self.get_piece(pinner_x, pinner_y)?;
+        let slides_this_way = match pinner.piece_type {
+            PieceType::Queen => true,
+            PieceType::Rook => dx == 0 || dy == 0,
+            PieceType::Bishop => dx != 0 && dy != 0,
+            _ => false
+        };
+        if pinner.piece_color == piece.piece_color || !slides_this_way {
+            return None;
+        }
+
+        let mut squares = towards_piece.path;
+        squares.extend(beyond.path);
+        squares.insert((x, y));
+        squares.insert((pinner_x, pinner_y));
+        Some(squares)
+    }
+
+    /// Squares of the pieces giving check to the side to move, two of them in a double check.
+    pub fn checkers(&self) -> Vec<(usize, usize)> {
+        match self.find_king() {
+            Some((x, y)) => self.attackers(x, y, self.player_to_move.opponent()),
+            None => Vec::new()
+        }
+    }
+
+    /// Squares of the pieces of color `by` attacking (x, y), ordered by row and then column.
+    pub fn attackers(&self, x: usize, y: usize, by: Color) -> Vec<(usize, usize)> {
+        let mut attackers = self.find_attackers(x, y, by, false);
+        attackers.sort_by_key(|&(x, y)| (y, x));
+        attackers
+    }
+
+    /// Whether any piece of color `by` attacks (x, y), stopping at the first attacker found.
+    pub fn is_square_attacked_by(&self, x: usize, y: usize, by: Color) -> bool {
+        !self.find_attackers(x, y, by, true).is_empty()
+    }
+
+    /// How many pieces of `color` attack each square, indexed [y][x] like the board. Sliding pieces
+    /// stop at the first piece in their way, and squares held by `color`'s own pieces count their
+    /// defenders. Overlaying the maps of both colors shows which squares are contested.
+    pub fn attack_heatmap(&self, color: Color) -> [[u8; BOARD_SIZE]; BOARD_SIZE] {
+        let mut heatmap = [[0; BOARD_SIZE]; BOARD_SIZE];
+        for (y, row) in heatmap.iter_mut().enumerate() {
+            for (x, count) in row.iter_mut().enumerate() {
+                *count = self.find_attackers(x, y, color, false).len() as u8;
+            }
+        }
+        heatmap
+    }
+
+    fn find_attackers(&self, x: usize, y: usize, by: Color, first_only: bool) -> Vec<(usize, usize)> {
+        // pawns only capture up the board, so the opponent's attacks are scanned with the board flipped
+        let flip = by != self.player_to_move;
+        let mut game = self.seen_by(by);
+        let target = if flip { (x, BOARD_SIZE - 1 - y) } else { (x, y) };
+        // captures only reach occupied squares, so stand something on an empty target
+        if game.board.tiles[target.1][target.0].is_none() {
+            game.board.tiles[target.1][target.0] = Some(PAWN_WHITE);
+        }
+
+        let mut attackers = Vec::new();
+        for ay in 0..BOARD_SIZE {
+            for ax in 0..BOARD_SIZE {
+                if let Some(piece) = game.get_piece(ax, ay) {
+                    if piece.piece_color == by && (ax, ay) != target && game.get_pseudo_captures(ax, ay).contains(&target) {
+                        attackers.push((ax, if flip { BOARD_SIZE - 1 - ay } else { ay }));
+                        if first_only {
+                            return attackers;
+                        }
+                    }
+                }
+            }
+        }
+        attackers
+    }
+
+    /// Number of pseudo-legal moves for the pieces of `color`, captures of own pieces excluded.
+    pub(crate) fn mobility(&self, color: Color) -> usize {
+        // pawns only advance up the board, so the opponent's moves are counted with the board flipped
+        let game = self.seen_by(color);
+
+        let mut count = 0;
+        for y in 0..BOARD_SIZE {
+            for x in 0..BOARD_SIZE {
+                if let Some(piece) = game.get_piece(x, y) {
+                    if piece.piece_color == color {
+                        count += game.get_pseudo_moves(x, y).into_iter()
+                            .filter(|&(x, y)| game.get_piece(x, y).is_none_or(|target| target.piece_color != color))
+                            .count();
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    /// Static exchange evaluation of moving the piece on `from` to `to`: the material won or lost in
+    /// centipawns for the mover if both sides keep recapturing on `to` with their least valuable attacker
+    /// for as long as it pays off.
+    pub fn see(&self, from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> i32 {
+        let see_value = |piece: Piece| piece.piece_type.value();
+        let moved = match self.get_piece(from_x, from_y) {
+            Some(piece) => piece,
+            None => return 0
+        };
+
+        let mut game = self.seen_by(self.player_to_move);
+        let mut gains = vec![self.get_piece(to_x, to_y).map_or(0, see_value)];
+        let mut on_target = moved;
+        game.board.tiles[to_y][to_x] = Some(moved);
+        game.board.tiles[from_y][from_x] = None;
+
+        let mut side = moved.piece_color.opponent();
+        loop {
+            let attacker = game.attackers(to_x, to_y, side).into_iter()
+                .min_by_key(|&(x, y)| game.get_piece(x, y).map_or(0, see_value));
+            let (x, y) = match attacker {
+                Some(square) => square,
+                None => break
+            };
+            gains.push(see_value(on_target) - gains[gains.len() - 1]);
+            on_target = game.get_piece(x, y).expect("attackers are occupied squares");
+            game.board.tiles[to_y][to_x] = Some(on_target);
+            game.board.tiles[y][x] = None;
+            side = side.opponent();
+        }
+
+        // either side may stop recapturing, so fold the gains back from the end of the sequence
+        while gains.len() > 1 {
+            let last = gains.pop().expect("gains isn't empty");
+            let previous = gains.last_mut().expect("gains isn't empty");
+            *previous = -(-*previous).max(last);
+        }
+        gains[0]
+    }
+
+    /// Parses and plays a move in UCI notation such as "e2e4", returning false if it can't be parsed or is illegal.
+    pub fn try_make_move_uci(&mut self, uci: &str) -> bool {
+        match notation::parse_uci(self, uci) {
+            Some(m) => self.try_play_move(m).is_ok(),
+            None => false
+        }
+    }
+
+    /// Parses and plays a move in SAN such as "Nf3", returning false if it can't be parsed or is illegal.
+    pub fn try_make_move_san(&mut self, san: &str) -> bool {
+        match notation::parse_san(self, san) {
+            Some(m) => self.try_play_move(m).is_ok(),
+            None => false
+        }
+    }
+
+    /// Plays moves given in UCI or SAN one after another, e.g. `&["e4", "e7e5", "Nf3"]`. Stops at the
+    /// first move that can't be parsed or isn't legal and returns its index, keeping the moves before it.
+    pub fn apply_moves(&mut self, moves: &[&str]) -> Result<(), usize> {
+        for (i, m) in moves.iter().enumerate() {
+            if !self.try_make_move_uci(m) && !self.try_make_move_san(m) {
+                return Err(i);
+            }
+        }
+        Ok(())
+    }
+
+    /// The promotion choices of a legal promotion move that would give check.
+    pub fn promotion_checks(&mut self, from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> Vec<PieceType> {
+        let mut checks = Vec::new();
+        let is_promotion = to_y == BOARD_SIZE - 1 && self.get_piece(from_x, from_y).is_some_and(|piece| piece.piece_type == PieceType::Pawn);
+        if !is_promotion || !self.is_legal_move(from_x, from_y, to_x, to_y) {
+            return checks;
+        }
+        for piece_type in PROMOTION_PIECE_TYPES {
+            let move_info = self.make_move(from_x, from_y, to_x, to_y, Some(piece_type));
+            self.end_turn();
+            if self.in_check() {
+                checks.push(piece_type);
+            }
+            self.unplay_move(move_info);
+        }
+        checks
+    }
+
+    /// The squares holding a `piece_color` `piece_type`, ordered by row and then column.
+    pub fn find_pieces(&self, piece_type: PieceType, piece_color: Color) -> Vec<(usize, usize)> {
+        let piece = Piece::new(piece_type, piece_color);
+        let mut squares = Vec::new();
+        for y in 0..BOARD_SIZE {
+            for x in 0..BOARD_SIZE {
+                if self.get_piece(x, y) == Some(piece) {
+                    squares.push((x, y));
+                }
+            }
+        }
+        squares
+    }
+
+    /// How many pieces of `piece_type` `piece_color` has on the board.
+    pub fn count(&self, piece_type: PieceType, piece_color: Color) -> usize {
+        let piece = Piece::new(piece_type, piece_color);
+        self.board.tiles.iter().flatten().filter(|&&tile| tile == Some(piece)).count()
+    }
+
+    pub fn total_pieces(&self) -> usize {
+        self.board.tiles.iter().flatten().filter(|tile| tile.is_some()).count()
+    }
+
+    pub fn is_double_push(&self, from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> bool {
+        if let Some(piece) = self.get_piece(from_x, from_y) {
+            if piece.piece_type == PieceType::Pawn && from_x == to_x {
+                return self.oriented(from_y) == pawn_home_rank(piece.piece_color) && from_y.abs_diff(to_y) == 2;
+            }
+        }
+        false
+    }
+
+    /// The number of legal moves for the side to move, the same as get_all_legal_moves().len()
+    /// without collecting them.
+    pub fn legal_move_count(&mut self) -> usize {
+        let mut count = 0;
+        self.for_each_legal_move(&mut |piece, _, to_y| {
+            count += if piece.piece_type == PieceType::Pawn && to_y == BOARD_SIZE - 1 { PROMOTION_PIECE_TYPES.len() } else { 1 };
+            true
+        });
+        count
+    }
+
+    /// Whether the side to move has any legal move, stopping at the first one found.
+    pub fn has_legal_move(&mut self) -> bool {
+        !self.for_each_legal_move(&mut |_, _, _| false)
+    }
+
+    /// Hands the moving piece and destination of each legal move to `visit` without allocating,
+    /// stopping as soon as it returns false. Returns whether every move was visited.
+    fn for_each_legal_move(&mut self, visit: &mut impl FnMut(Piece, usize, usize) -> bool) -> bool {
+        for y in 0..BOARD_SIZE {
+            for x in 0..BOARD_SIZE {
+                let Some(piece) = self.get_piece(x, y).filter(|piece| piece.piece_color == self.player_to_move) else {
+                    continue;
+                };
+                // the squares are gathered on the stack first, since trying a move changes the board being walked
+                let mut destinations = [(0, 0); MAX_PSEUDO_MOVES];
+                let mut len = 0;
+                self.for_each_pseudo_move(x, y, &mut |to_x, to_y| {
+                    destinations[len] = (to_x, to_y);
+                    len += 1;
+                    true
+                });
+                for &(to_x, to_y) in &destinations[..len] {
+                    if !self.can_be_here(to_x, to_y) {
+                        continue;
+                    }
+                    let move_info = self.make_move(x, y, to_x, to_y, None);
+                    let checked = self.in_check();
+                    self.restore_move(move_info);
+                    if !checked && !visit(piece, to_x, to_y) {
+                        return false;
                     }
                 }
             }
         }
-        return true;
+        true
     }
 
     pub fn is_checkmate(&mut self) -> bool {
-        !self.can_make_any_move() && self.in_check()
+        !self.has_legal_move() && self.in_check()
     }
 
     pub fn is_stalemate(&mut self) -> bool {
-        !self.can_make_any_move() && !self.in_check()
+        !self.has_legal_move() && !self.in_check()
+    }
+
+    /// `color` offers a draw, which stands until the other side accepts it or makes a move.
+    pub fn offer_draw(&mut self, color: Color) {
+        if self.declared_result.is_none() {
+            self.draw_offer = Some(color);
+        }
+    }
+
+    /// `color` accepts the other side's draw offer and the game is drawn by agreement.
+    /// Returns false if there's no such offer or the game was already decided.
+    pub fn accept_draw(&mut self, color: Color) -> bool {
+        if self.declared_result.is_some() || self.draw_offer != Some(color.opponent()) {
+            return false;
+        }
+        self.draw_offer = None;
+        self.declared_result = Some(GameResult::Draw(DrawReason::Agreement));
+        true
+    }
+
+    /// `color` resigns and the other side wins, unless the game was already decided.
+    pub fn resign(&mut self, color: Color) {
+        if self.declared_result.is_none() {
+            self.draw_offer = None;
+            self.declared_result = Some(match color {
+                Color::White => GameResult::BlackWins,
+                Color::Black => GameResult::WhiteWins
+            });
+        }
+    }
+
+    /// How the game ended, or None while it's still going. A resignation or agreed draw is
+    /// reported as is; otherwise the result follows from checkmate or stalemate on the board.
+    pub fn result(&mut self) -> Option<GameResult> {
+        if let Some(result) = self.declared_result {
+            return Some(result);
+        }
+        if self.has_legal_move() {
+            return None;
+        }
+        if !self.in_check() {
+            return Some(GameResult::Draw(DrawReason::Stalemate));
+        }
+        Some(match self.player_to_move {
+            Color::White => GameResult::BlackWins,
+            Color::Black => GameResult::WhiteWins
+        })
+    }
+
+    /// Whether try_make_move ends the game in a draw once a move brings the halfmove clock to
+    /// fifty moves or repeats a position for the third time, so result() reports it. Off by
+    /// default, leaving it to the players to claim these draws.
+    pub fn set_auto_draw(&mut self, enabled: bool) {
+        self.auto_draw = enabled;
+    }
+
+    /// Whether the legal moves of a position are generated once and kept until a move is made or
+    /// taken back, which helps a UI asking for a selected piece's moves every frame. Off by default,
+    /// as a search changes the position after nearly every call and would only pay for the copies.
+    pub fn set_legal_move_cache(&mut self, enabled: bool) {
+        self.cache_legal_moves = enabled;
+        self.legal_move_cache.0 = None;
+    }
+
+    /// Passes the turn to the other side without moving a piece, which search uses to see if a
+    /// position is good enough to win even when the opponent gets a free move. Refused while in
+    /// check, where passing would leave the king to be taken. Take it back with unmake_null_move;
+    /// undo_move refuses until then.
+    pub fn make_null_move(&mut self) -> bool {
+        if self.in_check() {
+            return false;
+        }
+        self.null_moves.push(self.history.len());
+        self.end_turn();
+        true
+    }
+
+    /// Takes back a null move, returning false unless one was the last thing played.
+    pub fn unmake_null_move(&mut self) -> bool {
+        if !self.after_null_move() {
+            return false;
+        }
+        self.null_moves.pop();
+        self.undo_turn();
+        true
+    }
+
+    /// Whether the last thing played was a null move rather than a move.
+    pub(crate) fn after_null_move(&self) -> bool {
+        self.null_moves.last() == Some(&self.history.len())
+    }
+
+    /// The square a pawn skipped with a double push on the last move, which is the en passant
+    /// target. Only known from the history, so a game set up from FEN has none until a move is made.
+    pub(crate) fn en_passant_square(&self) -> Option<(usize, usize)> {
+        let last = self.history.last().filter(|_| !self.after_null_move())?;
+        if last.moved.piece_type != PieceType::Pawn || last.from_y.abs_diff(last.to_y) != 2 {
+            return None;
+        }
+        // the last move was made from the other side of the board, so its rows count from the far end
+        Some((last.to_x, BOARD_SIZE - 1 - (last.from_y + last.to_y) / 2))
+    }
+
+    /// Whether fifty moves by each side have passed without a capture or a pawn move.
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.halfmove_clock >= 100
+    }
+
+    /// How many times the current position has come up in the game, this time included, as
+    /// judged by same_position. Only the moves since the last capture or pawn move are looked
+    /// at, since no position from before one of those can come back, and none from before a null
+    /// move still on the board either.
+    pub fn repetition_count(&mut self) -> usize {
+        let key = self.position_key();
+        let since_null_move = self.history.len() - self.null_moves.last().copied().unwrap_or(0);
+        let plies = (self.halfmove_clock as usize).min(since_null_move);
+        let mut taken_back = Vec::with_capacity(plies);
+        let mut count = 1;
+        for _ in 0..plies {
+            let move_info = self.history.pop().expect("there are at least as many moves as plies");
+            self.unplay_move(move_info);
+            taken_back.push(move_info);
+            if self.position_key() == key {
+                count += 1;
+            }
+        }
+        for move_info in taken_back.into_iter().rev() {
+            self.apply_legal_move(move_info.as_move());
+        }
+        count
+    }
+
+    pub fn is_threefold_repetition(&mut self) -> bool {
+        self.repetition_count() >= 3
+    }
+
+    fn declare_automatic_draw(&mut self) {
+        // a move that mates or stalemates ends the game on the board instead
+        if !self.auto_draw || self.declared_result.is_some() || !self.has_legal_move() {
+            return;
+        }
+        if self.is_fifty_move_draw() {
+            self.declared_result = Some(GameResult::Draw(DrawReason::FiftyMoveRule));
+        } else if self.is_threefold_repetition() {
+            self.declared_result = Some(GameResult::Draw(DrawReason::ThreefoldRepetition));
+        }
     }
 
     pub fn try_make_move(&mut self, from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> bool {
-        match self.player_to_move() {
-            Color::White => {
-                if self.is_legal_move(from_x, from_y, to_x, to_y) {
-                    self.make_move(from_x, from_y, to_x, to_y);
-                    self.swap_turn();
-                    self.flip_board();
-                    return true
-                }
-                false
-            }
-            Color::Black => {
-                if self.is_legal_move(from_x, from_y, to_x, to_y) {
-                    self.make_move(from_x, from_y, to_x, to_y);
-                    self.swap_turn();
-                    self.flip_board();
-                    return true
+        self.try_make_move_checked(from_x, from_y, to_x, to_y).is_ok()
+    }
+
+    /// Like try_make_move, but says why an illegal move was turned down.
+    pub fn try_make_move_checked(&mut self, from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> Result<(), MoveError> {
+        self.try_play_move(Move::new(from_x, from_y, to_x, to_y))
+    }
+
+    /// Whether `m` moves a piece of the side to move the way that piece moves, onto a square it may
+    /// take or enter, without looking at whether it leaves the mover's king in check. A pinned piece's
+    /// moves are pseudo-legal but not legal.
+    pub fn is_pseudo_legal_move(&self, m: Move) -> bool {
+        self.check_promotion(m).is_ok() && self.check_pseudo_legal(m.from_x, m.from_y, m.to_x, m.to_y).is_ok()
+    }
+
+    /// Like try_make_move_checked, but takes a Move so a promotion can name the piece to promote to.
+    pub fn try_play_move(&mut self, m: Move) -> Result<(), MoveError> {
+        self.check_full_move(m)?;
+        // moving instead of accepting turns the offer down
+        if self.draw_offer == Some(self.player_to_move.opponent()) {
+            self.draw_offer = None;
+        }
+        let move_info = self.play_move(m);
+        self.history.push(move_info);
+        self.redo.clear();
+        self.declare_automatic_draw();
+        Ok(())
+    }
+
+    /// Plays a move without checking that it's legal, recording it in the history like try_make_move.
+    /// The redo stack is left alone, so a search can run in the middle of stepping through a game
+    /// as long as it takes its moves back with retract_move.
+    pub(crate) fn apply_legal_move(&mut self, m: Move) {
+        self.make_move_unchecked(m);
+    }
+
+    /// Plays `m` as fast as possible for an engine's own search, returning what's needed to take it
+    /// back with unmake_move. Nothing about the move is checked: an illegal move is played anyway
+    /// and leaves the game in a state the rules can't reach, and moving from an empty square panics.
+    /// Only pass moves from get_all_legal_moves, or ones try_play_move would accept. The move goes into
+    /// the history, but the redo stack is left alone.
+    pub fn make_move_unchecked(&mut self, m: Move) -> MoveInfo {
+        let move_info = self.play_move(m);
+        self.history.push(move_info);
+        move_info
+    }
+
+    /// Takes back a move made with make_move_unchecked, restoring the castling rights and halfmove
+    /// clock from before it. Returns false, leaving the game as it is, unless `move_info` is the last
+    /// move played and no null move was made after it.
+    pub fn unmake_move(&mut self, move_info: MoveInfo) -> bool {
+        if self.history.last() != Some(&move_info) || self.after_null_move() {
+            return false;
+        }
+        self.retract_move();
+        true
+    }
+
+    /// Whether both games have the same pieces on the same squares, the same side to move, castling
+    /// rights and en passant square, regardless of how they got there. This is the notion of position
+    /// used for repetitions.
+    pub fn same_position(&self, other: &Game) -> bool {
+        self.player_to_move == other.player_to_move
+            && self.board == other.board
+            && self.castling == other.castling
+            && self.en_passant_square() == other.en_passant_square()
+    }
+
+    pub fn position_key(&self) -> PositionKey {
+        PositionKey {
+            tiles: self.board.tiles,
+            player_to_move: self.player_to_move,
+            castling: self.castling,
+            en_passant: self.en_passant_square()
+        }
+    }
+
+    /// Starts a new game from the standard setup, clearing the history.
+    pub fn reset(&mut self) {
+        *self = Game::default();
+    }
+
+    /// Takes back the last move made with try_make_move, returning false if there is none or a
+    /// null move was made after it, which unmake_null_move has to take back first.
+    /// The move can be played again with redo_move.
+    pub fn undo_move(&mut self) -> bool {
+        if self.after_null_move() {
+            return false;
+        }
+        match self.history.pop() {
+            Some(move_info) => {
+                self.unplay_move(move_info);
+                self.redo.push(move_info);
+                // a draw the rules declared no longer holds once the move that reached it is gone
+                if matches!(self.declared_result, Some(GameResult::Draw(DrawReason::FiftyMoveRule | DrawReason::ThreefoldRepetition))) {
+                    self.declared_result = None;
                 }
-                false
+                true
             }
+            None => false
         }
     }
+
+    /// Takes back a move played with apply_legal_move, without touching the redo stack.
+    pub(crate) fn retract_move(&mut self) {
+        if let Some(move_info) = self.history.pop() {
+            self.unplay_move(move_info);
+        }
+    }
+
+    /// Plays the last move taken back with undo_move again, returning false if there is none or
+    /// a null move is on the board. Making any other move clears the moves that could be redone.
+    pub fn redo_move(&mut self) -> bool {
+        if self.after_null_move() {
+            return false;
+        }
+        match self.redo.pop() {
+            Some(move_info) => {
+                let move_info = self.play_move(move_info.as_move());
+                self.history.push(move_info);
+                self.declare_automatic_draw();
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Undoes moves until the game is back at `ply`, keeping them for redo_move. Returns false if
+    /// `ply` is ahead of the game, or, having undone everything it could, if the history doesn't
+    /// reach back that far.
+    pub fn undo_to_ply(&mut self, ply: usize) -> bool {
+        while self.ply > ply {
+            if !self.undo_move() {
+                return false;
+            }
+        }
+        self.ply == ply
+    }
 }
 
 impl Board {
-    pub fn new(setup: [[Option<Piece>; 8]; 8]) -> Self {
+    pub fn new(setup: [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE]) -> Self {
         Board {
             tiles: setup
         }
     }
+
+    /// Reads a board drawn with FEN letters and '.' for empty squares, the first row being rank 8.
+    /// Spaces between squares are ignored, so rows can be written as "r n b q k b n r".
+    pub fn from_ascii(rows: &[&str; BOARD_SIZE]) -> Result<Board, FenError> {
+        let mut tiles: [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE] = [[None; BOARD_SIZE]; BOARD_SIZE];
+        for (i, row) in rows.iter().enumerate() {
+            let squares: Vec<char> = row.chars().filter(|c| !c.is_whitespace()).collect();
+            if squares.len() != BOARD_SIZE {
+                return Err(FenError::InvalidPlacement(row.to_string()));
+            }
+            for (x, &c) in squares.iter().enumerate() {
+                if c != '.' {
+                    tiles[BOARD_SIZE - 1 - i][x] = Some(Piece::from_fen_char(c).ok_or(FenError::InvalidPiece(c))?);
+                }
+            }
+        }
+        Ok(Board::new(tiles))
+    }
+
+    /// The squares by row, oriented the same way as Game::get_piece.
+    pub fn tiles(&self) -> &[[Option<Piece>; BOARD_SIZE]; BOARD_SIZE] {
+        &self.tiles
+    }
+}
+
+/// `board[(x, y)]` is the same square as `Game::get_piece(x, y)`.
+impl Index<(usize, usize)> for Board {
+    type Output = Option<Piece>;
+
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        &self.tiles[y][x]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Board {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
+        &mut self.tiles[y][x]
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::new(BOARD_DEFAULT_SETUP)
+    }
+}
+
+impl FromStr for Game {
+    type Err = FenError;
+
+    fn from_str(fen: &str) -> Result<Self, Self::Err> {
+        Game::from_fen(fen)
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new(Board::default())
+    }
 }
\ No newline at end of file
@@ -0,0 +1,81 @@
+use std::time::{Duration, Instant};
+
+use crate::chess::{Color, ALL_COLORS};
+
+/// A chess clock with the same starting time for both sides and an optional increment
+/// added after every move. Kept apart from Game, which doesn't need to know about time.
+#[derive(Clone, Copy, Debug)]
+pub struct Clock {
+    white: Duration,
+    black: Duration,
+    increment: Duration,
+    running: Option<(Color, Instant)>
+}
+
+impl Clock {
+    pub fn new(time: Duration, increment: Duration) -> Self {
+        Clock {
+            white: time,
+            black: time,
+            increment,
+            running: None
+        }
+    }
+
+    fn time_mut(&mut self, color: Color) -> &mut Duration {
+        match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black
+        }
+    }
+
+    /// Starts counting down for `color`, stopping the other side's time first if it was running.
+    pub fn start(&mut self, color: Color) {
+        self.stop();
+        self.running = Some((color, Instant::now()));
+    }
+
+    /// Stops the running side's time, if any, without adding the increment.
+    pub fn stop(&mut self) {
+        if let Some((color, started)) = self.running.take() {
+            let time = self.time_mut(color);
+            *time = time.saturating_sub(started.elapsed());
+        }
+    }
+
+    /// Ends the running side's move: their time stops, they get the increment unless their flag
+    /// has already fallen, and the opponent's time starts.
+    pub fn press(&mut self) {
+        if let Some((color, _)) = self.running {
+            self.stop();
+            let increment = self.increment;
+            let time = self.time_mut(color);
+            if !time.is_zero() {
+                *time += increment;
+            }
+            self.start(color.opponent());
+        }
+    }
+
+    /// Time left for `color`, counting the current move if their time is running.
+    pub fn remaining(&self, color: Color) -> Duration {
+        let time = match color {
+            Color::White => self.white,
+            Color::Black => self.black
+        };
+        match self.running {
+            Some((running, started)) if running == color => time.saturating_sub(started.elapsed()),
+            _ => time
+        }
+    }
+
+    /// The side whose time is running, if any.
+    pub fn running(&self) -> Option<Color> {
+        self.running.map(|(color, _)| color)
+    }
+
+    /// The side that has run out of time, if either has.
+    pub fn flagged(&self) -> Option<Color> {
+        ALL_COLORS.into_iter().find(|&color| self.remaining(color).is_zero())
+    }
+}
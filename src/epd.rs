@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use crate::chess::Game;
+use crate::fen::{self, FenError};
+
+#[derive(Debug, PartialEq)]
+pub enum EpdError {
+    Fen(FenError),
+    UnterminatedString(String),
+    InvalidOperation(String),
+}
+
+impl From<FenError> for EpdError {
+    fn from(error: FenError) -> Self {
+        EpdError::Fen(error)
+    }
+}
+
+/// Splits the operations after the position into `opcode operand;` pairs. Semicolons inside
+/// quoted operands don't end the operation, and the quotes themselves are dropped.
+fn parse_operations(operations: &str) -> Result<HashMap<String, String>, EpdError> {
+    let mut map = HashMap::new();
+    let mut operation = String::new();
+    let mut quoted = false;
+    for c in operations.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                operation.push(c);
+            },
+            ';' if !quoted => {
+                let (opcode, operand) = parse_operation(&operation)?;
+                map.insert(opcode, operand);
+                operation.clear();
+            },
+            _ => operation.push(c)
+        }
+    }
+    if quoted {
+        return Err(EpdError::UnterminatedString(operation));
+    }
+    if !operation.trim().is_empty() {
+        return Err(EpdError::InvalidOperation(operation.trim().to_string()));
+    }
+    Ok(map)
+}
+
+fn parse_operation(operation: &str) -> Result<(String, String), EpdError> {
+    let operation = operation.trim();
+    let (opcode, operand) = operation.split_once(char::is_whitespace).unwrap_or((operation, ""));
+    if opcode.is_empty() || opcode.contains('"') {
+        return Err(EpdError::InvalidOperation(operation.to_string()));
+    }
+    let operand = operand.trim();
+    let operand = operand.strip_prefix('"').and_then(|o| o.strip_suffix('"')).unwrap_or(operand);
+    Ok((opcode.to_string(), operand.to_string()))
+}
+
+/// Parses a line of Extended Position Description, e.g.
+/// `"6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id \"back rank\";"`, into the position and its
+/// operations keyed by opcode, so a test suite can compare `bm` against the engine's move.
+pub fn parse(epd: &str) -> Result<(Game, HashMap<String, String>), EpdError> {
+    let mut rest = epd.trim_start();
+    let mut fields = Vec::new();
+    while fields.len() < 4 {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        if end == 0 {
+            break;
+        }
+        fields.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+
+    let game = fen::parse_fields(&fields)?;
+    let operations = parse_operations(rest)?;
+    Ok((game, operations))
+}
@@ -0,0 +1,291 @@
+use crate::chess::{Color, Game, PieceType, ALL_COLORS, ALL_PIECE_TYPES, BOARD_SIZE};
+
+// piece-square tables from white's side, written with rank 8 on top
+const PAWN_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+const KNIGHT_TABLE: [i32; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   5,  15,  20,  20,  15,   5, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   5,  10,  15,  15,  10,   5, -30,
+    -40, -20,   0,   5,   5,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+const BISHOP_TABLE: [i32; 64] = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   5,   5,  10,  10,   5,   5, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,  10,  10,  10,  10,  10,  10, -10,
+    -10,   5,   0,   0,   0,   0,   5, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+const ROOK_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+const QUEEN_TABLE: [i32; 64] = [
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+     -5,   0,   5,   5,   5,   5,   0,  -5,
+      0,   0,   5,   5,   5,   5,   0,  -5,
+    -10,   5,   5,   5,   5,   5,   0, -10,
+    -10,   0,   5,   0,   0,   0,   0, -10,
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+const KING_TABLE: [i32; 64] = [
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+     20,  20,   0,   0,   0,   0,  20,  20,
+     20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+/// Centipawns per pseudo-legal move in evaluate_mobility.
+const MOBILITY_WEIGHT: i32 = 4;
+
+// kings are on the board in every position, so they add nothing to the material count
+fn piece_value(piece_type: PieceType) -> i32 {
+    match piece_type {
+        PieceType::King => 0,
+        _ => piece_type.value()
+    }
+}
+
+fn piece_square_bonus(piece_type: PieceType, piece_color: Color, x: usize, rank: usize) -> i32 {
+    let table = match piece_type {
+        PieceType::Pawn => &PAWN_TABLE,
+        PieceType::Knight => &KNIGHT_TABLE,
+        PieceType::Bishop => &BISHOP_TABLE,
+        PieceType::Rook => &ROOK_TABLE,
+        PieceType::Queen => &QUEEN_TABLE,
+        PieceType::King => &KING_TABLE,
+    };
+    // black reads the same tables mirrored vertically
+    let row = match piece_color {
+        Color::White => BOARD_SIZE - 1 - rank,
+        Color::Black => rank
+    };
+    table[row * BOARD_SIZE + x]
+}
+
+/// Centipawn bonuses for the positional terms that can be tuned, passed to the evaluate_*_with methods.
+/// The methods without `_with` use the defaults.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EvalWeights {
+    /// A rook on a file with no pawns at all.
+    pub rook_open_file: i32,
+    /// A rook on a file with only enemy pawns.
+    pub rook_half_open_file: i32,
+    /// A rook on the opponent's second rank, the seventh from its own side.
+    pub rook_on_seventh: i32,
+    /// Having two bishops when the other side doesn't.
+    pub bishop_pair: i32
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        EvalWeights {
+            rook_open_file: 25,
+            rook_half_open_file: 10,
+            rook_on_seventh: 20,
+            bishop_pair: 40
+        }
+    }
+}
+
+/// Piece counts for both sides, as returned by Game::material_balance.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Material {
+    white: [u32; 6],
+    black: [u32; 6]
+}
+
+impl Material {
+    /// How many pieces of `piece_type` and `piece_color` are on the board.
+    pub fn count(&self, piece_type: PieceType, piece_color: Color) -> u32 {
+        match piece_color {
+            Color::White => self.white[piece_type as usize],
+            Color::Black => self.black[piece_type as usize]
+        }
+    }
+
+    /// Material value of one side in centipawns, kings not counted.
+    pub fn value(&self, piece_color: Color) -> i32 {
+        let counts = match piece_color {
+            Color::White => &self.white,
+            Color::Black => &self.black
+        };
+        ALL_PIECE_TYPES.iter().map(|&piece_type| counts[piece_type as usize] as i32 * piece_value(piece_type)).sum()
+    }
+
+    /// White's material minus Black's in centipawns, e.g. 300 for White being a knight up.
+    pub fn balance(&self) -> i32 {
+        self.value(Color::White) - self.value(Color::Black)
+    }
+}
+
+fn signed(piece_color: Color, score: i32) -> i32 {
+    match piece_color {
+        Color::White => score,
+        Color::Black => -score
+    }
+}
+
+impl Game {
+    /// Material balance in centipawns from White's perspective.
+    pub fn evaluate(&self) -> i32 {
+        let mut score = 0;
+        for y in 0..BOARD_SIZE {
+            for x in 0..BOARD_SIZE {
+                if let Some(piece) = self.get_piece(x, y) {
+                    score += signed(piece.piece_color(), piece_value(piece.piece_type()));
+                }
+            }
+        }
+        score
+    }
+
+    /// Counts the pieces of each type for both sides in a single pass over the board.
+    pub fn material_balance(&self) -> Material {
+        let mut material = Material::default();
+        for row in self.board().tiles() {
+            for piece in row.iter().flatten() {
+                let counts = match piece.piece_color() {
+                    Color::White => &mut material.white,
+                    Color::Black => &mut material.black
+                };
+                counts[piece.piece_type() as usize] += 1;
+            }
+        }
+        material
+    }
+
+    /// Material plus midgame piece-square bonuses in centipawns from White's perspective.
+    pub fn evaluate_positional(&self) -> i32 {
+        let mut score = 0;
+        for y in 0..BOARD_SIZE {
+            for x in 0..BOARD_SIZE {
+                if let Some(piece) = self.get_piece(x, y) {
+                    let bonus = piece_square_bonus(piece.piece_type(), piece.piece_color(), x, self.oriented(y));
+                    score += signed(piece.piece_color(), piece_value(piece.piece_type()) + bonus);
+                }
+            }
+        }
+        score
+    }
+
+    /// For a lone king against king and pawn, whether the defending king catches the pawn by the
+    /// rule of the square: Some(true) if it can reach the promotion square in time, which draws,
+    /// and Some(false) if the pawn runs through and promotes. The attacking king is only looked at
+    /// to make sure it's the one piece besides the pawn, so it blocking or shielding the pawn isn't
+    /// accounted for. None for any other material.
+    pub fn king_catches_pawn(&self) -> Option<bool> {
+        let material = self.material_balance();
+        let attacker = ALL_COLORS.into_iter().find(|&color| material.count(PieceType::Pawn, color) == 1)?;
+        let defender = attacker.opponent();
+        if material.value(attacker) != PieceType::Pawn.value() || material.value(defender) != 0 {
+            return None;
+        }
+
+        let &(pawn_x, pawn_y) = self.find_pieces(PieceType::Pawn, attacker).first()?;
+        let &(king_x, king_y) = self.find_pieces(PieceType::King, defender).first()?;
+        let (pawn_rank, king_rank) = (self.oriented(pawn_y), self.oriented(king_y));
+        let (promotion_rank, start_rank) = match attacker {
+            Color::White => (BOARD_SIZE - 1, 1),
+            Color::Black => (0, BOARD_SIZE - 2)
+        };
+
+        let mut pawn_moves = pawn_rank.abs_diff(promotion_rank);
+        if pawn_rank == start_rank {
+            pawn_moves -= 1;
+        }
+        let king_moves = king_x.abs_diff(pawn_x).max(king_rank.abs_diff(promotion_rank));
+        // moving first wins the king a tempo, and it may also take the new queen a move late
+        let tempo = if self.player_to_move() == defender { 1 } else { 0 };
+        Some(king_moves <= pawn_moves + tempo)
+    }
+
+    /// Bonuses for rooks on open and half-open files and on the seventh rank, in centipawns from
+    /// White's perspective, with the default EvalWeights.
+    pub fn evaluate_rook_placement(&self) -> i32 {
+        self.evaluate_rook_placement_with(&EvalWeights::default())
+    }
+
+    /// Like evaluate_rook_placement, with the bonuses taken from `weights`.
+    pub fn evaluate_rook_placement_with(&self, weights: &EvalWeights) -> i32 {
+        let pawns_on_file = |x: usize, color: Color| {
+            (0..BOARD_SIZE).any(|y| self.get_piece(x, y).is_some_and(|piece| piece.piece_type() == PieceType::Pawn && piece.piece_color() == color))
+        };
+
+        let mut score = 0;
+        for color in ALL_COLORS {
+            let seventh_rank = match color {
+                Color::White => BOARD_SIZE - 2,
+                Color::Black => 1
+            };
+            for (x, y) in self.find_pieces(PieceType::Rook, color) {
+                let mut bonus = 0;
+                if !pawns_on_file(x, color) {
+                    bonus += if pawns_on_file(x, color.opponent()) { weights.rook_half_open_file } else { weights.rook_open_file };
+                }
+                if self.oriented(y) == seventh_rank {
+                    bonus += weights.rook_on_seventh;
+                }
+                score += signed(color, bonus);
+            }
+        }
+        score
+    }
+
+    /// Bonus for the bishop pair in centipawns from White's perspective, for whichever side has two or
+    /// more bishops while the other has fewer, with the default EvalWeights.
+    pub fn evaluate_bishop_pair(&self) -> i32 {
+        self.evaluate_bishop_pair_with(&EvalWeights::default())
+    }
+
+    /// Like evaluate_bishop_pair, with the bonus taken from `weights`.
+    pub fn evaluate_bishop_pair_with(&self, weights: &EvalWeights) -> i32 {
+        let material = self.material_balance();
+        let has_pair = |color: Color| material.count(PieceType::Bishop, color) >= 2;
+        match (has_pair(Color::White), has_pair(Color::Black)) {
+            (true, false) => weights.bishop_pair,
+            (false, true) => -weights.bishop_pair,
+            _ => 0
+        }
+    }
+
+    /// Mobility bonus in centipawns from White's perspective, a few centipawns for every
+    /// pseudo-legal move one side has over the other.
+    pub fn evaluate_mobility(&self) -> i32 {
+        let white = self.mobility(Color::White) as i32;
+        let black = self.mobility(Color::Black) as i32;
+        MOBILITY_WEIGHT * (white - black)
+    }
+}
@@ -1 +1,183 @@
-// TODO
\ No newline at end of file
+use crate::chess::{Board, CastlingRights, Color, Game, Piece, PieceType, BOARD_SIZE};
+
+/// The standard starting position, the same as Game::default().
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, PartialEq)]
+pub enum FenError {
+    InvalidSquare(String),
+    OccupiedSquare(String),
+    MissingField(&'static str),
+    TooManyFields,
+    InvalidPlacement(String),
+    InvalidPiece(char),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidCounter(String),
+}
+
+/// Parses a square name such as "e4" into board coordinates, with rank 1 at y = 0.
+pub fn parse_square(square: &str) -> Option<(usize, usize)> {
+    let mut chars = square.chars();
+    let file = chars.next()?;
+    let rank = chars.next()?;
+    if chars.next().is_some() {
+        return None;
+    }
+    if !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+        return None;
+    }
+    Some((file as usize - 'a' as usize, rank as usize - '1' as usize))
+}
+
+/// Sets up a game with White to move from a list of pieces on named squares,
+/// e.g. `(PieceType::King, Color::White, "e1")`.
+pub fn from_piece_list(pieces: &[(PieceType, Color, &str)]) -> Result<Game, FenError> {
+    let mut tiles: [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE] = [[None; BOARD_SIZE]; BOARD_SIZE];
+    for &(piece_type, piece_color, square) in pieces {
+        let (x, y) = parse_square(square).ok_or_else(|| FenError::InvalidSquare(square.to_string()))?;
+        if tiles[y][x].is_some() {
+            return Err(FenError::OccupiedSquare(square.to_string()));
+        }
+        tiles[y][x] = Some(Piece::new(piece_type, piece_color));
+    }
+    Ok(Game::new(Board::new(tiles)))
+}
+
+fn parse_placement(placement: &str) -> Result<Board, FenError> {
+    let invalid = || FenError::InvalidPlacement(placement.to_string());
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != BOARD_SIZE {
+        return Err(invalid());
+    }
+
+    let mut tiles: [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE] = [[None; BOARD_SIZE]; BOARD_SIZE];
+    // the first rank listed is rank 8
+    for (i, rank) in ranks.iter().enumerate() {
+        let y = BOARD_SIZE - 1 - i;
+        let mut x = 0;
+        for c in rank.chars() {
+            if let Some(skip) = c.to_digit(10) {
+                if skip == 0 || skip as usize > BOARD_SIZE {
+                    return Err(invalid());
+                }
+                x += skip as usize;
+            } else {
+                if x >= BOARD_SIZE {
+                    return Err(invalid());
+                }
+                tiles[y][x] = Some(Piece::from_fen_char(c).ok_or_else(invalid)?);
+                x += 1;
+            }
+        }
+        if x != BOARD_SIZE {
+            return Err(invalid());
+        }
+    }
+    Ok(Board::new(tiles))
+}
+
+/// Parses the fields shared by FEN and EPD: placement, side to move, castling and en passant,
+/// followed by the optional halfmove clock and fullmove number.
+pub(crate) fn parse_fields(fields: &[&str]) -> Result<Game, FenError> {
+    let field = |i: usize, name: &'static str| fields.get(i).copied().ok_or(FenError::MissingField(name));
+    if fields.len() > 6 {
+        return Err(FenError::TooManyFields);
+    }
+
+    let board = parse_placement(field(0, "placement")?)?;
+
+    let player_to_move = match field(1, "side to move")? {
+        "w" => Color::White,
+        "b" => Color::Black,
+        side => return Err(FenError::InvalidSideToMove(side.to_string()))
+    };
+
+    let castling = field(2, "castling")?;
+    let valid_castling = castling == "-" || (!castling.is_empty() && castling.chars().all(|c| "KQkq".contains(c)));
+    if !valid_castling {
+        return Err(FenError::InvalidCastling(castling.to_string()));
+    }
+    let castling = CastlingRights::new(castling.contains('K'), castling.contains('Q'), castling.contains('k'), castling.contains('q'));
+
+    // a parsed game has no last move to take en passant against, so the square is only checked for being well-formed
+    let en_passant = field(3, "en passant")?;
+    if en_passant != "-" && parse_square(en_passant).is_none_or(|(_, rank)| rank != 2 && rank != 5) {
+        return Err(FenError::InvalidEnPassant(en_passant.to_string()));
+    }
+
+    let halfmove_clock = match fields.get(4) {
+        Some(&clock) => clock.parse::<u32>().map_err(|_| FenError::InvalidCounter(clock.to_string()))?,
+        None => 0
+    };
+    let fullmove_number = match fields.get(5) {
+        Some(&number) => match number.parse::<u32>() {
+            Ok(n) if n > 0 => n,
+            _ => return Err(FenError::InvalidCounter(number.to_string()))
+        },
+        None => 1
+    };
+
+    Ok(Game::from_position(board, player_to_move, castling, halfmove_clock, fullmove_number))
+}
+
+/// Parses a position in Forsyth-Edwards Notation. The halfmove clock and fullmove number
+/// may be left out, in which case the game starts at move 1.
+pub fn parse(fen: &str) -> Result<Game, FenError> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    parse_fields(&fields)
+}
+
+fn square_name(x: usize, rank: usize) -> String {
+    format!("{}{}", (b'a' + x as u8) as char, rank + 1)
+}
+
+/// Writes the position as FEN. The en passant square is given right after a double push.
+pub fn to_fen(game: &Game) -> String {
+    let mut placement = String::new();
+    for rank in (0..BOARD_SIZE).rev() {
+        let mut empty = 0;
+        for x in 0..BOARD_SIZE {
+            match game.get_piece(x, game.oriented(rank)) {
+                Some(piece) => {
+                    if empty > 0 {
+                        placement.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    placement.push(piece.to_fen_char());
+                },
+                None => empty += 1
+            }
+        }
+        if empty > 0 {
+            placement.push_str(&empty.to_string());
+        }
+        if rank > 0 {
+            placement.push('/');
+        }
+    }
+
+    let side = match game.player_to_move() {
+        Color::White => "w",
+        Color::Black => "b"
+    };
+
+    let rights = game.castling_rights();
+    let mut castling: String = [
+        (rights.kingside(Color::White), 'K'),
+        (rights.queenside(Color::White), 'Q'),
+        (rights.kingside(Color::Black), 'k'),
+        (rights.queenside(Color::Black), 'q')
+    ].iter().filter(|(right, _)| *right).map(|&(_, c)| c).collect();
+    if castling.is_empty() {
+        castling.push('-');
+    }
+
+    let en_passant = match game.en_passant_square() {
+        Some((x, y)) => square_name(x, game.oriented(y)),
+        None => "-".to_string()
+    };
+
+    format!("{} {} {} {} {} {}", placement, side, castling, en_passant, game.halfmove_clock(), game.fullmove_number())
+}
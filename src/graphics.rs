@@ -1,4 +1,6 @@
-#[derive(Clone, Copy)]
+use std::io::{self, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Color {
     r: u8,
     g: u8,
@@ -6,6 +8,9 @@ pub struct Color {
 }
 
 impl Color {
+    /// The color key blit treats as see-through when it's a cell's background. It's a real color
+    /// too, so a layer can't show pure magenta.
+    pub const TRANSPARENT: Color = Color::new(255, 0, 255);
 
     pub const fn new(r: u8, g: u8, b: u8) -> Self {
         Color {
@@ -15,16 +20,97 @@ impl Color {
         }
     }
 
-    fn to_bg_string(&self) -> String {
+    /// A color from a 0xRRGGBB integer, as palettes are often written. The top byte, usually alpha, is ignored.
+    pub const fn from_u32(rgb: u32) -> Self {
+        Color::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    }
+
+    /// The color as a 0xRRGGBB integer, with the top byte zero.
+    pub const fn to_u32(&self) -> u32 {
+        (self.r as u32) << 16 | (self.g as u32) << 8 | self.b as u32
+    }
+
+    fn to_bg_string(self) -> String {
         format!("\x1B[48;2;{};{};{}m", self.r, self.g, self.b)
     }
 
-    fn to_fg_string(&self) -> String {
+    fn to_fg_string(self) -> String {
         format!("\x1B[38;2;{};{};{}m", self.r, self.g, self.b)
     }
 
+    /// Linearly interpolates towards `other`, with `t` clamped to 0.0..=1.0.
+    pub fn lerp(&self, other: Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b))
+    }
+
+    /// Moves `factor` of the way towards black, clamped to 0.0..=1.0.
+    pub fn darken(&self, factor: f32) -> Color {
+        self.lerp(Color::new(0, 0, 0), factor)
+    }
+
+    /// Moves `factor` of the way towards white, clamped to 0.0..=1.0.
+    pub fn lighten(&self, factor: f32) -> Color {
+        self.lerp(Color::new(255, 255, 255), factor)
+    }
+
+    pub fn invert(&self) -> Color {
+        Color::new(255 - self.r, 255 - self.g, 255 - self.b)
+    }
+
+    /// Perceptual luminance in the range 0.0 (black) to 1.0 (white).
+    pub fn luminance(&self) -> f32 {
+        (0.2126 * self.r as f32 + 0.7152 * self.g as f32 + 0.0722 * self.b as f32) / 255.0
+    }
+
+    /// Black or white, whichever stays legible when drawn on top of this color.
+    pub fn readable_text_color(&self) -> Color {
+        if self.luminance() > 0.5 {
+            Color::new(0, 0, 0)
+        } else {
+            Color::new(255, 255, 255)
+        }
+    }
+
 }
     
+/// Switches the terminal to its alternate screen buffer, leaving the normal one as it was
+/// until leave_alt_screen.
+pub fn enter_alt_screen(out: &mut impl Write) -> io::Result<()> {
+    write!(out, "\x1B[?1049h")
+}
+
+pub fn leave_alt_screen(out: &mut impl Write) -> io::Result<()> {
+    write!(out, "\x1B[?1049l")
+}
+
+pub fn hide_cursor(out: &mut impl Write) -> io::Result<()> {
+    write!(out, "\x1B[?25l")
+}
+
+pub fn show_cursor(out: &mut impl Write) -> io::Result<()> {
+    write!(out, "\x1B[?25h")
+}
+
+fn crop_layer<T: Copy>(layer: &[Vec<T>], x: usize, y: usize, width: usize, height: usize) -> Vec<Vec<T>> {
+    layer[y..y + height].iter().map(|row| row[x..x + width].to_vec()).collect()
+}
+
+fn flip_layer<T: Copy>(layer: &[Vec<T>], flip_x: bool, flip_y: bool) -> Vec<Vec<T>> {
+    let mut layer = layer.to_vec();
+    if flip_y {
+        layer.reverse();
+    }
+    if flip_x {
+        for row in layer.iter_mut() {
+            row.reverse();
+        }
+    }
+    layer
+}
+
+#[derive(Debug, PartialEq, Eq)]
 pub struct Screen {
     width: usize,
     height: usize,
@@ -45,27 +131,87 @@ impl Screen {
         }
     }
 
-    pub fn clear(&mut self, background: Option<Color>, foreground: Option<Color>, sprite: Option<char>) -> () {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                if let Some(bg) = background {
-                    self.background[y][x] = bg;
-                }
-                if let Some(fg) = foreground {
-                    self.foreground[y][x] = fg;
-                }
-                if let Some(c) = sprite {
-                    self.sprites[y][x] = c;
-                }
+    /// A screen that blit leaves no mark with until something is drawn on it: every background is
+    /// Color::TRANSPARENT and every sprite a space.
+    pub fn new_transparent(width: usize, height: usize) -> Self {
+        let mut screen = Screen::new(width, height);
+        screen.clear(Some(Color::TRANSPARENT), None, None);
+        screen
+    }
+
+    /// Draws `source` over this screen with its top left corner at (`x`, `y`), clipped to this screen.
+    /// A Color::TRANSPARENT background keeps the background underneath, and a space keeps the sprite
+    /// and foreground underneath.
+    pub fn blit(&mut self, source: &Screen, x: usize, y: usize) {
+        for sy in 0..source.height.min(self.height.saturating_sub(y)) {
+            for sx in 0..source.width.min(self.width.saturating_sub(x)) {
+                let (bg, fg, c) = source.get_pixel(sx, sy);
+                let bg = Some(bg).filter(|&bg| bg != Color::TRANSPARENT);
+                let (fg, c) = if c == ' ' { (None, None) } else { (Some(fg), Some(c)) };
+                self.set_pixel(x + sx, y + sy, bg, fg, c);
             }
         }
     }
 
+    pub fn clear(&mut self, background: Option<Color>, foreground: Option<Color>, sprite: Option<char>) {
+        self.clear_region(0, 0, self.width, self.height, background, foreground, sprite);
+    }
+
+    /// Like clear, but only for the `width` by `height` rectangle at (`x`, `y`), clipped to the screen.
+    #[allow(clippy::too_many_arguments)]
+    pub fn clear_region(&mut self, x: usize, y: usize, width: usize, height: usize, background: Option<Color>, foreground: Option<Color>, sprite: Option<char>) {
+        for y in y..(y.saturating_add(height)).min(self.height) {
+            for x in x..(x.saturating_add(width)).min(self.width) {
+                self.set_pixel(x, y, background, foreground, sprite);
+            }
+        }
+    }
+
+    /// Copies the `width` by `height` rectangle at (`x`, `y`) into a new screen. The rectangle is
+    /// clamped to this screen, so the copy can come out smaller than asked for.
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Screen {
+        let (x, y) = (x.min(self.width), y.min(self.height));
+        let width = width.min(self.width - x);
+        let height = height.min(self.height - y);
+        Screen {
+            width,
+            height,
+            foreground: crop_layer(&self.foreground, x, y, width, height),
+            background: crop_layer(&self.background, x, y, width, height),
+            sprites: crop_layer(&self.sprites, x, y, width, height)
+        }
+    }
+
+    /// A copy mirrored left to right.
+    pub fn flipped_horizontal(&self) -> Screen {
+        self.transformed(true, false)
+    }
+
+    /// A copy mirrored top to bottom.
+    pub fn flipped_vertical(&self) -> Screen {
+        self.transformed(false, true)
+    }
+
+    /// A copy turned upside down, as for viewing the board from Black's side.
+    pub fn rotated_180(&self) -> Screen {
+        self.transformed(true, true)
+    }
+
+    fn transformed(&self, flip_x: bool, flip_y: bool) -> Screen {
+        Screen {
+            width: self.width,
+            height: self.height,
+            foreground: flip_layer(&self.foreground, flip_x, flip_y),
+            background: flip_layer(&self.background, flip_x, flip_y),
+            sprites: flip_layer(&self.sprites, flip_x, flip_y)
+        }
+    }
+
     pub fn get_pixel(&self, x: usize, y: usize) -> (Color, Color, char) {
         (self.background[y][x], self.foreground[y][x], self.sprites[y][x])
     }
 
-    pub fn set_pixel(&mut self, x: usize, y: usize, background: Option<Color>, foreground: Option<Color>, sprite: Option<char>) -> () {
+    pub fn set_pixel(&mut self, x: usize, y: usize, background: Option<Color>, foreground: Option<Color>, sprite: Option<char>) {
         if let Some(bg) = background {
             self.background[y][x] = bg;
         }
@@ -77,21 +223,308 @@ impl Screen {
         }
     }
 
+    /// Like get_pixel, but None outside the screen instead of panicking.
+    pub fn try_get_pixel(&self, x: usize, y: usize) -> Option<(Color, Color, char)> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(self.get_pixel(x, y))
+    }
+
+    /// Like set_pixel, but does nothing and returns false outside the screen instead of panicking.
+    pub fn try_set_pixel(&mut self, x: usize, y: usize, background: Option<Color>, foreground: Option<Color>, sprite: Option<char>) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        self.set_pixel(x, y, background, foreground, sprite);
+        true
+    }
+
+    /// Paints `background` over the cells connected to (`x`, `y`) by edges that share its exact
+    /// background color. Uses an explicit stack rather than recursion, so large areas are fine.
+    pub fn flood_fill(&mut self, x: usize, y: usize, background: Color) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let target = self.background[y][x];
+        if target == background {
+            return;
+        }
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+            if self.background[y][x] != target {
+                continue;
+            }
+            self.background[y][x] = background;
+            if x > 0 {
+                stack.push((x - 1, y));
+            }
+            if x + 1 < self.width {
+                stack.push((x + 1, y));
+            }
+            if y > 0 {
+                stack.push((x, y - 1));
+            }
+            if y + 1 < self.height {
+                stack.push((x, y + 1));
+            }
+        }
+    }
+
+    /// Writes `text` one cell per char starting at (`x`, `y`), cut off at the right edge of the screen.
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str, foreground: Option<Color>, background: Option<Color>) {
+        if y >= self.height {
+            return;
+        }
+        for (i, c) in text.chars().enumerate() {
+            if x + i >= self.width {
+                break;
+            }
+            self.set_pixel(x + i, y, background, foreground, Some(c));
+        }
+    }
+
+    /// Like draw_text, but breaks lines on spaces to fit within `width` columns. Words longer than
+    /// `width` are split. Returns the number of lines used.
+    pub fn draw_text_wrapped(&mut self, x: usize, y: usize, width: usize, text: &str, foreground: Option<Color>, background: Option<Color>) -> usize {
+        if width == 0 {
+            return 0;
+        }
+        let mut lines: Vec<String> = Vec::new();
+        let mut line = String::new();
+        for word in text.split(' ').filter(|word| !word.is_empty()) {
+            let mut word: Vec<char> = word.chars().collect();
+            let line_len = line.chars().count();
+            if line_len > 0 && line_len + 1 + word.len() <= width {
+                line.push(' ');
+                line.extend(word);
+                continue;
+            }
+            if line_len > 0 {
+                lines.push(std::mem::take(&mut line));
+            }
+            while word.len() > width {
+                let rest = word.split_off(width);
+                lines.push(word.into_iter().collect());
+                word = rest;
+            }
+            line = word.into_iter().collect();
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+
+        for (i, line) in lines.iter().enumerate() {
+            self.draw_text(x, y + i, line, foreground, background);
+        }
+        lines.len()
+    }
+
+    /// Outlines the `width` by `height` rectangle at (`x`, `y`) with box-drawing chars in `color`,
+    /// leaving the background and the inside untouched. Parts past the edge of the screen are clipped.
+    pub fn draw_border(&mut self, x: usize, y: usize, width: usize, height: usize, color: Color) {
+        if width == 0 || height == 0 || x >= self.width || y >= self.height {
+            return;
+        }
+        let (right, bottom) = (x.saturating_add(width - 1), y.saturating_add(height - 1));
+        for py in y..=bottom.min(self.height.saturating_sub(1)) {
+            for px in x..=right.min(self.width.saturating_sub(1)) {
+                let c = match (px == x, px == right, py == y, py == bottom) {
+                    (true, _, true, _) => '┌',
+                    (_, true, true, _) => '┐',
+                    (true, _, _, true) => '└',
+                    (_, true, _, true) => '┘',
+                    (_, _, true, _) | (_, _, _, true) => '─',
+                    (true, _, _, _) | (_, true, _, _) => '│',
+                    _ => continue
+                };
+                self.set_pixel(px, py, None, Some(color), Some(c));
+            }
+        }
+    }
+
+    /// Sets the background of the cells along the circle of `radius` around (`cx`, `cy`).
+    pub fn draw_circle(&mut self, cx: usize, cy: usize, radius: usize, color: Color) {
+        self.draw_ellipse(cx, cy, radius, 1.0, color, false);
+    }
+
+    /// Like draw_circle, but also sets the background of every cell inside the circle.
+    pub fn fill_circle(&mut self, cx: usize, cy: usize, radius: usize, color: Color) {
+        self.draw_ellipse(cx, cy, radius, 1.0, color, true);
+    }
+
+    /// Draws a circle of `radius` around (`cx`, `cy`) whose vertical radius is scaled by `y_scale`,
+    /// using the midpoint ellipse algorithm. Terminal cells are about twice as tall as they are
+    /// wide, so a `y_scale` of 0.5 makes it look round. Parts past the edge of the screen are clipped.
+    pub fn draw_ellipse(&mut self, cx: usize, cy: usize, radius: usize, y_scale: f32, color: Color, filled: bool) {
+        let rx = radius as i64;
+        let ry = (radius as f32 * y_scale.max(0.0)).round() as i64;
+        // flattened to a single row, which the algorithm below would draw as just the center
+        if ry == 0 {
+            self.plot_ellipse_points(cx, cy, rx, 0, color, true);
+            return;
+        }
+        let (rx2, ry2) = (rx * rx, ry * ry);
+        let (mut x, mut y) = (0, ry);
+        let (mut px, mut py) = (0, 2 * rx2 * y);
+
+        // the slope is shallower than -1 near the top, so step along x there and along y after
+        let mut p = ry2 - rx2 * ry + rx2 / 4;
+        while px < py {
+            self.plot_ellipse_points(cx, cy, x, y, color, filled);
+            x += 1;
+            px += 2 * ry2;
+            if p < 0 {
+                p += ry2 + px;
+            } else {
+                y -= 1;
+                py -= 2 * rx2;
+                p += ry2 + px - py;
+            }
+        }
+
+        p = ry2 * (2 * x + 1) * (2 * x + 1) / 4 + rx2 * (y - 1) * (y - 1) - rx2 * ry2;
+        while y >= 0 {
+            self.plot_ellipse_points(cx, cy, x, y, color, filled);
+            y -= 1;
+            py -= 2 * rx2;
+            if p > 0 {
+                p += rx2 - py;
+            } else {
+                x += 1;
+                px += 2 * ry2;
+                p += rx2 - py + px;
+            }
+        }
+    }
+
+    // the four mirrored points at (`dx`, `dy`) from the center, or the rows between them when filled
+    fn plot_ellipse_points(&mut self, cx: usize, cy: usize, dx: i64, dy: i64, color: Color, filled: bool) {
+        let (cx, cy) = (cx as i64, cy as i64);
+        for y in [cy - dy, cy + dy] {
+            if y < 0 || y >= self.height as i64 {
+                continue;
+            }
+            let xs: Vec<i64> = if filled {
+                (cx - dx..=cx + dx).collect()
+            } else {
+                vec![cx - dx, cx + dx]
+            };
+            for x in xs {
+                if x >= 0 && x < self.width as i64 {
+                    self.background[y as usize][x as usize] = color;
+                }
+            }
+        }
+    }
+
     pub fn render(&self, flip_x: bool , flip_y: bool) -> String {
-        let mut result = String::new();
+        self.render_with_cell_width(flip_x, flip_y, 3)
+    }
+
+    /// Renders every cell `cell_width` terminal columns wide with the char centered, padded with spaces.
+    /// A width of 1 emits a single column per cell, 2 suits wide glyphs, and `render` uses 3.
+    pub fn render_with_cell_width(&self, flip_x: bool, flip_y: bool, cell_width: usize) -> String {
+        let mut buffer: Vec<u8> = Vec::new();
+        self.write_cells(&mut buffer, flip_x, flip_y, cell_width).expect("writing to a Vec can't fail");
+        String::from_utf8(buffer).expect("rendered output is valid utf-8")
+    }
+
+    /// Renders the background colors only, two rows per line of output: each terminal cell is a '▀'
+    /// with the upper row as its foreground and the lower row as its background, which makes every
+    /// screen cell close to square. An odd last row gets a black lower half. Sprites aren't drawn.
+    pub fn render_half_block(&self, flip_x: bool, flip_y: bool) -> String {
+        let mut output = String::new();
+        for row in (0..self.height).step_by(2) {
+            for x in 0..self.width {
+                let x = if flip_x { self.width - 1 - x } else { x };
+                let top = if flip_y { self.height - 1 - row } else { row };
+                let bottom = if row + 1 >= self.height {
+                    Color::new(0, 0, 0)
+                } else if flip_y {
+                    self.background[self.height - 2 - row][x]
+                } else {
+                    self.background[row + 1][x]
+                };
+                output.push_str(&self.background[top][x].to_fg_string());
+                output.push_str(&bottom.to_bg_string());
+                output.push('▀');
+            }
+            output.push('\n');
+        }
+        output.push_str("\x1B[0m");
+        output
+    }
+
+    /// Like render, but streams the output into `out` instead of building a String.
+    pub fn render_to(&self, out: &mut impl Write, flip_x: bool, flip_y: bool) -> io::Result<()> {
+        self.write_cells(out, flip_x, flip_y, 3)
+    }
+
+    /// Draws the screen to stdout in place of what the terminal showed before: homes the cursor,
+    /// clears, writes the render and flushes, with the cursor hidden while drawing.
+    pub fn present(&self, flip_x: bool, flip_y: bool) -> io::Result<()> {
+        self.present_to(&mut io::stdout().lock(), flip_x, flip_y)
+    }
+
+    /// Like present, but writes to `out`.
+    pub fn present_to(&self, out: &mut impl Write, flip_x: bool, flip_y: bool) -> io::Result<()> {
+        hide_cursor(out)?;
+        write!(out, "\x1B[H\x1B[2J")?;
+        self.write_cells(out, flip_x, flip_y, 3)?;
+        show_cursor(out)?;
+        out.flush()
+    }
+
+    /// Like present, for a terminal that shows `previous` as drawn by present: only the cells that
+    /// differ are redrawn and nothing is cleared, so there's no flicker. Falls back to present if the
+    /// sizes don't match.
+    pub fn present_diff(&self, previous: &Screen, flip_x: bool, flip_y: bool) -> io::Result<()> {
+        self.present_diff_to(&mut io::stdout().lock(), previous, flip_x, flip_y)
+    }
+
+    /// Like present_diff, but writes to `out`.
+    pub fn present_diff_to(&self, out: &mut impl Write, previous: &Screen, flip_x: bool, flip_y: bool) -> io::Result<()> {
+        if (previous.width, previous.height) != (self.width, self.height) {
+            return self.present_to(out, flip_x, flip_y);
+        }
+        hide_cursor(out)?;
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let x = if flip_x { self.width - 1 - column } else { column };
+                let y = if flip_y { self.height - 1 - row } else { row };
+                if self.get_pixel(x, y) == previous.get_pixel(x, y) {
+                    continue;
+                }
+                // cursor positions count from 1, and every cell is 3 columns wide as in render
+                write!(out, "\x1B[{};{}H", row + 1, 3 * column + 1)?;
+                self.write_cell(out, x, y, " ", " ")?;
+            }
+        }
+        write!(out, "\x1B[0m\x1B[{};1H", self.height + 1)?;
+        show_cursor(out)?;
+        out.flush()
+    }
+
+    fn write_cells(&self, out: &mut impl Write, flip_x: bool, flip_y: bool, cell_width: usize) -> io::Result<()> {
+        let padding = cell_width.max(1) - 1;
+        let left = " ".repeat(padding / 2);
+        let right = " ".repeat(padding - padding / 2);
         for y in 0..self.height {
             for x in 0..self.width {
                 let x = if flip_x { self.width - 1 - x } else { x };
                 let y = if flip_y { self.height - 1 - y } else { y };
-                let bg = self.background[y][x].to_bg_string();
-                let fg = self.foreground[y][x].to_fg_string();
-                let c = self.sprites[y][x];
-                result.push_str(format!("{}{} {} ", bg, fg, c).as_str());
+                self.write_cell(out, x, y, &left, &right)?;
             }
-            result.push('\n');
+            writeln!(out)?;
         }
-        result.push_str("\x1B[0m");
-        result
+        write!(out, "\x1B[0m")
+    }
+
+    fn write_cell(&self, out: &mut impl Write, x: usize, y: usize, left: &str, right: &str) -> io::Result<()> {
+        let bg = self.background[y][x].to_bg_string();
+        let fg = self.foreground[y][x].to_fg_string();
+        write!(out, "{}{}{}{}{}", bg, fg, left, self.sprites[y][x], right)
     }
 
     pub fn width(&self) -> usize {
@@ -101,4 +534,54 @@ impl Screen {
     pub fn height(&self) -> usize {
         self.height
     }
-}
\ No newline at end of file
+}
+
+/// Named screens of the same size stacked on top of each other, so parts of a UI like the board,
+/// highlights and overlays can be drawn separately and put together each frame.
+pub struct Layers {
+    width: usize,
+    height: usize,
+    // bottom first
+    layers: Vec<(String, Screen)>
+}
+
+impl Layers {
+    pub fn new(width: usize, height: usize) -> Self {
+        Layers {
+            width,
+            height,
+            layers: Vec::new()
+        }
+    }
+
+    /// Puts a new transparent layer on top of the others and returns it for drawing. A layer that
+    /// already has the name is removed first.
+    pub fn add_layer(&mut self, name: &str) -> &mut Screen {
+        self.remove_layer(name);
+        self.layers.push((name.to_string(), Screen::new_transparent(self.width, self.height)));
+        &mut self.layers.last_mut().expect("a layer was just added").1
+    }
+
+    /// Takes the layer out of the stack, returning None if there's no layer with the name.
+    pub fn remove_layer(&mut self, name: &str) -> Option<Screen> {
+        let i = self.layers.iter().position(|(layer, _)| layer == name)?;
+        Some(self.layers.remove(i).1)
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&Screen> {
+        self.layers.iter().find(|(layer, _)| layer == name).map(|(_, screen)| screen)
+    }
+
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut Screen> {
+        self.layers.iter_mut().find(|(layer, _)| layer == name).map(|(_, screen)| screen)
+    }
+
+    /// Blits every layer from the bottom up onto a new screen.
+    pub fn compose(&self) -> Screen {
+        let mut screen = Screen::new(self.width, self.height);
+        for (_, layer) in &self.layers {
+            screen.blit(layer, 0, 0);
+        }
+        screen
+    }
+}
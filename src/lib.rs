@@ -1,72 +1,2100 @@
+pub mod book;
 pub mod chess;
+pub mod clock;
+pub mod epd;
+pub mod eval;
 pub mod fen;
 pub mod graphics;
+pub mod notation;
+pub mod render;
+pub mod search;
+pub mod zobrist;
 
 #[cfg(test)]
 mod tests {
     use crate::chess;
+    use crate::clock;
+    use crate::epd;
+    use crate::eval;
+    use crate::fen;
     use crate::graphics;
-    use crate::graphics::Screen;
-    use std::collections::HashSet;
+    use crate::notation;
+    use crate::render;
+    use crate::search;
+    use std::collections::{HashMap, HashSet};
     use std::io;
 
     const RANK_CHARS: [char; 8] = ['1', '2', '3', '4', '5', '6', '7', '8'];
     const FILE_CHARS: [char; 8] = ['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'];
 
-    fn piece_char(piece: &chess::Piece) -> char {
-        match (piece.piece_type(), piece.piece_color()) {
-            (chess::PieceType::Pawn, chess::Color::White) => '♙',
-            (chess::PieceType::Pawn, chess::Color::Black) => '♟',
-            (chess::PieceType::Bishop, chess::Color::White) => '♗',
-            (chess::PieceType::Bishop, chess::Color::Black) => '♝',
-            (chess::PieceType::Knight, chess::Color::White) => '♘',
-            (chess::PieceType::Knight, chess::Color::Black) => '♞',
-            (chess::PieceType::Rook, chess::Color::White) => '♖',
-            (chess::PieceType::Rook, chess::Color::Black) => '♜',
-            (chess::PieceType::Queen, chess::Color::White) => '♕',
-            (chess::PieceType::Queen, chess::Color::Black) => '♛',
-            (chess::PieceType::King, chess::Color::White) => '♔',
-            (chess::PieceType::King, chess::Color::Black) => '♚',
-        }
-    }
-
-    fn draw_piece(piece: &chess::Piece, x: usize, y: usize, screen: &mut graphics::Screen) -> () {
-        let fg = match piece.piece_color() {
-            chess::Color::White => graphics::Color::new(255, 255, 255),
-            chess::Color::Black => graphics::Color::new(0, 0, 0),
+    #[test]
+    fn readable_text_color() {
+        let black = graphics::Color::new(0, 0, 0);
+        let white = graphics::Color::new(255, 255, 255);
+        assert_eq!(graphics::Color::new(196, 196, 196).readable_text_color(), black);
+        assert_eq!(graphics::Color::new(32, 32, 32).readable_text_color(), white);
+        assert_eq!(graphics::Color::new(0, 0, 255).readable_text_color(), white);
+    }
+
+    #[test]
+    fn color_u32() {
+        assert_eq!(graphics::Color::from_u32(0x00C4C4C4), graphics::Color::new(196, 196, 196));
+        assert_eq!(graphics::Color::from_u32(0xFF102030), graphics::Color::new(0x10, 0x20, 0x30));
+        assert_eq!(graphics::Color::new(0x10, 0x20, 0x30).to_u32(), 0x102030);
+        assert_eq!(graphics::Color::from_u32(0xFF102030).to_u32(), 0x102030);
+        assert_eq!(graphics::Color::TRANSPARENT.to_u32(), 0xFF00FF);
+    }
+
+    #[test]
+    fn darken_lighten_invert() {
+        let color = graphics::Color::new(100, 200, 50);
+        assert_eq!(color.darken(0.5), graphics::Color::new(50, 100, 25));
+        assert_eq!(color.lighten(1.0), graphics::Color::new(255, 255, 255));
+        assert_eq!(color.darken(2.0), graphics::Color::new(0, 0, 0));
+        assert_eq!(color.lighten(-1.0), color);
+        assert_eq!(color.invert(), graphics::Color::new(155, 55, 205));
+    }
+
+    #[test]
+    fn from_piece_list() {
+        let game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "e1"),
+            (chess::PieceType::Queen, chess::Color::White, "d1"),
+            (chess::PieceType::King, chess::Color::Black, "e8"),
+        ]).unwrap();
+
+        let expected = [
+            ((4, 0), chess::PieceType::King, chess::Color::White),
+            ((3, 0), chess::PieceType::Queen, chess::Color::White),
+            ((4, 7), chess::PieceType::King, chess::Color::Black),
+        ];
+        for ((x, y), piece_type, piece_color) in expected {
+            let piece = game.get_piece(x, y).unwrap();
+            assert_eq!(piece.piece_type(), piece_type);
+            assert_eq!(piece.piece_color(), piece_color);
+        }
+        assert_eq!(game.player_to_move(), chess::Color::White);
+
+        assert_eq!(fen::from_piece_list(&[(chess::PieceType::King, chess::Color::White, "i1")]).err(), Some(fen::FenError::InvalidSquare("i1".to_string())));
+        assert_eq!(fen::from_piece_list(&[(chess::PieceType::King, chess::Color::White, "e1"), (chess::PieceType::Queen, chess::Color::White, "e1")]).err(), Some(fen::FenError::OccupiedSquare("e1".to_string())));
+    }
+
+    #[test]
+    fn double_push() {
+        let mut game = chess::Game::default();
+
+        assert!(game.is_double_push(4, 1, 4, 3));
+        assert!(!game.is_double_push(4, 1, 4, 2));
+        assert!(game.is_double_push(4, 6, 4, 4));
+        assert!(!game.is_double_push(4, 6, 4, 5));
+        assert!(!game.is_double_push(6, 0, 5, 2));
+
+        assert!(game.try_make_move(4, 1, 4, 3));
+        assert_eq!(game.player_to_move(), chess::Color::Black);
+        assert!(game.is_double_push(3, 1, 3, 3));
+        assert!(!game.is_double_push(3, 1, 3, 2));
+    }
+
+    #[test]
+    fn perft() {
+        let mut game = chess::Game::default();
+
+        assert_eq!(game.perft(0), 1);
+        assert_eq!(game.perft(1), 20);
+        assert_eq!(game.perft(2), 400);
+        assert_eq!(game.perft(3), 8902);
+
+        let divide = game.perft_divide(2);
+        assert_eq!(divide.len(), 20);
+        assert!(divide.iter().all(|&(_, nodes)| nodes == 20));
+        assert_eq!(divide.iter().map(|&(_, nodes)| nodes).sum::<u64>(), game.perft(2));
+    }
+
+    #[test]
+    fn animate_move() {
+        let game = chess::Game::default();
+        let background = || {
+            let mut screen = graphics::Screen::new(10, 10);
+            screen.clear(Some(graphics::Color::new(32, 32, 32)), None, Some(' '));
+            screen
+        };
+
+        let theme = render::BoardTheme::default();
+        let frames = render::animate_move(background, &game, (1, 1), (4, 1), (4, 3), 5, &theme);
+        assert_eq!(frames.len(), 5);
+
+        let first = frames.first().unwrap();
+        assert_eq!(first.get_pixel(5, 2).2, '♙');
+        assert_eq!(first.get_pixel(5, 4).2, ' ');
+
+        assert_eq!(frames[2].get_pixel(5, 2).2, ' ');
+        assert_eq!(frames[2].get_pixel(5, 3).2, '♙');
+
+        let last = frames.last().unwrap();
+        assert_eq!(last.get_pixel(5, 2).2, ' ');
+        assert_eq!(last.get_pixel(5, 4).2, '♙');
+
+        assert!(render::animate_move(background, &game, (1, 1), (4, 1), (4, 3), 0, &theme).is_empty());
+
+        let letters = render::BoardTheme { glyphs: |piece| piece.to_fen_char(), ..theme };
+        let frames = render::animate_move(background, &game, (1, 1), (4, 1), (4, 3), 3, &letters);
+        assert!(frames.iter().all(|frame| (0..10).any(|y| frame.get_pixel(5, y).2 == 'P')));
+        assert!(frames.iter().all(|frame| (0..10).all(|y| frame.get_pixel(5, y).2 != '♙')));
+    }
+
+    #[test]
+    fn checkers() {
+        let game = chess::Game::default();
+        assert!(game.checkers().is_empty());
+
+        let game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "e1"),
+            (chess::PieceType::King, chess::Color::Black, "a8"),
+            (chess::PieceType::Rook, chess::Color::Black, "e8"),
+        ]).unwrap();
+        assert_eq!(game.checkers(), vec![(4, 7)]);
+
+        let game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "e1"),
+            (chess::PieceType::King, chess::Color::Black, "a8"),
+            (chess::PieceType::Rook, chess::Color::Black, "e8"),
+            (chess::PieceType::Knight, chess::Color::Black, "d3"),
+            (chess::PieceType::Bishop, chess::Color::Black, "b4"),
+            (chess::PieceType::Pawn, chess::Color::White, "d2"),
+        ]).unwrap();
+        assert_eq!(game.checkers(), vec![(3, 2), (4, 7)]);
+    }
+
+    #[test]
+    fn total_pieces() {
+        let game = chess::Game::default();
+        assert_eq!(game.total_pieces(), 32);
+
+        let game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "e1"),
+            (chess::PieceType::King, chess::Color::Black, "e8"),
+        ]).unwrap();
+        assert_eq!(game.total_pieces(), 2);
+    }
+
+    #[test]
+    fn find_and_count_pieces() {
+        let mut game = chess::Game::default();
+        assert_eq!(game.count(chess::PieceType::Pawn, chess::Color::White), 8);
+        assert_eq!(game.count(chess::PieceType::Queen, chess::Color::Black), 1);
+        assert_eq!(game.find_pieces(chess::PieceType::Knight, chess::Color::White), vec![(1, 0), (6, 0)]);
+
+        // Black's pieces are at the top after a move, and captured ones are gone
+        assert_eq!(game.apply_moves(&["e4", "d5", "exd5"]), Ok(()));
+        assert_eq!(game.find_pieces(chess::PieceType::Rook, chess::Color::Black), vec![(0, 0), (7, 0)]);
+        assert_eq!(game.count(chess::PieceType::Pawn, chess::Color::Black), 7);
+        assert_eq!(game.find_pieces(chess::PieceType::Pawn, chess::Color::White).last(), Some(&(7, 6)));
+        assert!(fen::parse("8/8/8/8/8/8/8/8 w - -").unwrap().find_pieces(chess::PieceType::King, chess::Color::White).is_empty());
+    }
+
+    #[test]
+    fn in_check_by_each_piece() {
+        let in_check = |fen: &str| fen::parse(fen).unwrap().in_check();
+        assert!(!in_check(fen::STARTING_FEN));
+
+        // pawns capture towards the side they attack, whichever side is to move
+        assert!(in_check("4k3/8/8/8/8/8/3p4/4K3 w - -"));
+        assert!(!in_check("4k3/8/8/8/8/8/4p3/4K3 w - -"));
+        assert!(!in_check("4k3/8/8/8/8/8/8/3pK3 w - -"));
+        assert!(in_check("4k3/5P2/8/8/8/8/8/4K3 b - -"));
+        assert!(!in_check("4k3/8/5P2/8/8/8/8/4K3 b - -"));
+
+        assert!(in_check("4k3/8/8/8/8/5n2/8/4K3 w - -"));
+        assert!(in_check("4k3/8/8/8/1b6/8/8/4K3 w - -"));
+        assert!(!in_check("4k3/8/8/8/1b6/8/3P4/4K3 w - -"));
+        assert!(in_check("4k3/8/8/4r3/8/8/8/4K3 w - -"));
+        assert!(!in_check("4k3/8/8/4r3/8/8/4P3/4K3 w - -"));
+        assert!(in_check("4k3/8/8/8/7q/8/8/4K3 w - -"));
+        assert!(in_check("4k3/8/8/8/8/8/3k4/4K3 w - -"));
+        assert!(in_check("4k3/8/8/8/Q7/8/8/4K3 b - -"));
+
+        // only the side to move's king counts
+        assert!(!in_check("4k3/8/8/8/8/8/8/R3K3 w - -"));
+    }
+
+    #[test]
+    fn pseudo_legal_moves() {
+        // the bishop is pinned to its king by the rook
+        let mut game = fen::parse("4r1k1/8/8/8/8/8/4B3/4K3 w - -").unwrap();
+        let pinned = chess::Move::new(4, 1, 3, 2);
+        assert!(game.is_pseudo_legal_move(pinned));
+        assert_eq!(game.try_play_move(pinned), Err(chess::MoveError::LeavesKingInCheck));
+
+        assert!(!game.is_pseudo_legal_move(chess::Move::new(4, 1, 4, 2)));
+        assert!(!game.is_pseudo_legal_move(chess::Move::new(0, 0, 0, 1)));
+        assert!(!game.is_pseudo_legal_move(chess::Move::new(4, 7, 4, 6)));
+        assert!(game.is_pseudo_legal_move(chess::Move::new(4, 0, 3, 0)));
+        assert!(!game.is_pseudo_legal_move(chess::Move::new_promotion(4, 0, 3, 0, chess::PieceType::Queen)));
+    }
+
+    #[test]
+    fn pin_ray() {
+        // the rook on e4 is pinned by the rook on e7 and may take it
+        let mut game = fen::parse("6k1/4r3/8/8/4R3/8/b7/4K1NQ w - -").unwrap();
+        let ray = game.pin_ray(4, 3).unwrap();
+        assert_eq!(ray, HashSet::from([(4, 1), (4, 2), (4, 3), (4, 4), (4, 5), (4, 6)]));
+        let moves = game.get_legal_moves(4, 3);
+        assert!(moves.contains(&(4, 6)));
+        assert!(moves.iter().all(|square| ray.contains(square)));
+
+        // nothing is pinned along a line with no slider of the right kind at the end
+        assert_eq!(game.pin_ray(4, 0), None);
+        assert_eq!(game.pin_ray(6, 0), None);
+        assert_eq!(game.pin_ray(7, 0), None);
+        assert_eq!(game.pin_ray(0, 1), None);
+
+        // a bishop pins diagonally but not along a file
+        let game = fen::parse("4k3/8/8/8/8/8/3N4/2b1K3 w - -").unwrap();
+        assert_eq!(game.pin_ray(3, 1), None);
+        let game = fen::parse("4k3/8/8/8/8/1b6/8/3NK3 w - -").unwrap();
+        assert_eq!(game.pin_ray(3, 0), None);
+        let game = fen::parse("4k3/8/8/8/b7/8/2N5/3K4 w - -").unwrap();
+        assert_eq!(game.pin_ray(2, 1), Some(HashSet::from([(2, 1), (1, 2), (0, 3)])));
+    }
+
+    #[test]
+    fn try_make_move_notation() {
+        let mut game = chess::Game::default();
+
+        assert!(game.try_make_move_uci("e2e4"));
+        assert!(game.try_make_move_uci("e7e5"));
+        assert!(game.try_make_move_san("Nf3"));
+        assert!(game.try_make_move_san("Nc6"));
+        assert!(game.try_make_move_san("Bb5"));
+        assert!(!game.try_make_move_san("Ke6"));
+        assert!(!game.try_make_move_uci("d7d4"));
+        assert!(!game.try_make_move_uci("d7"));
+        assert!(!game.try_make_move_san("Zz9"));
+        assert!(game.try_make_move_san("d6"));
+        assert!(game.try_make_move_san("Bxc6+"));
+        assert!(game.try_make_move_san("bxc6"));
+
+        // black just moved, so rank 1 is at the bottom of the board again
+        let piece = game.get_piece(5, 2).unwrap();
+        assert_eq!(piece.piece_type(), chess::PieceType::Knight);
+        let piece = game.get_piece(2, 5).unwrap();
+        assert_eq!(piece.piece_type(), chess::PieceType::Pawn);
+        assert_eq!(piece.piece_color(), chess::Color::Black);
+        assert!(game.get_piece(5, 0).is_none());
+    }
+
+    #[test]
+    fn promotion_checks() {
+        let mut game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "a1"),
+            (chess::PieceType::Pawn, chess::Color::White, "e7"),
+            (chess::PieceType::King, chess::Color::Black, "d6"),
+        ]).unwrap();
+        assert_eq!(game.promotion_checks(4, 6, 4, 7), vec![chess::PieceType::Knight]);
+        assert!(game.promotion_checks(0, 0, 0, 1).is_empty());
+
+        let mut game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "a1"),
+            (chess::PieceType::Pawn, chess::Color::White, "e7"),
+            (chess::PieceType::King, chess::Color::Black, "h8"),
+        ]).unwrap();
+        assert_eq!(game.promotion_checks(4, 6, 4, 7), vec![chess::PieceType::Queen, chess::PieceType::Rook]);
+
+        assert!(game.try_make_move(4, 6, 4, 7));
+        let piece = game.get_piece(4, 0).unwrap();
+        assert_eq!(piece.piece_type(), chess::PieceType::Queen);
+        assert_eq!(piece.piece_color(), chess::Color::White);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut game = chess::Game::default();
+        assert!(game.try_make_move_uci("e2e4"));
+
+        let json = serde_json::to_string(&game).unwrap();
+        let mut restored: chess::Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.player_to_move(), chess::Color::Black);
+        assert_eq!(restored.total_pieces(), 32);
+        assert!(restored.get_piece(4, 3).is_none());
+        assert!(restored.try_make_move_uci("e7e5"));
+    }
+
+    #[test]
+    fn save_and_load_state() {
+        let mut game = fen::parse("r3k3/1P6/8/8/8/8/6p1/4K2R w K - 3 30").unwrap();
+        for m in ["b7a8q", "e8d7", "e1e2", "g2h1n", "a8a7", "d7e6", "a7a1", "h1g3"] {
+            assert!(game.try_make_move_uci(m), "{m}");
+        }
+        let state = game.save_state();
+        assert_eq!(state.start_fen(), "r3k3/1P6/8/8/8/8/6p1/4K2R w K - 3 30");
+        assert_eq!(state.moves().len(), 8);
+
+        let mut loaded = chess::Game::load_state(&state).unwrap();
+        assert_eq!(loaded.to_fen(), game.to_fen());
+        assert_eq!(loaded.captured_pieces(chess::Color::Black), game.captured_pieces(chess::Color::Black));
+        assert_eq!(loaded.repetition_count(), game.repetition_count());
+        while game.undo_move() {
+            assert!(loaded.undo_move());
+            assert_eq!(loaded.to_fen(), game.to_fen());
+        }
+        assert!(!loaded.undo_move());
+
+        let start = chess::Game::load_state(&chess::Game::default().save_state()).unwrap();
+        assert!(start == chess::Game::default());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_game_state() {
+        let mut game = chess::Game::default();
+        for m in ["e2e4", "d7d5", "e4d5", "d8d5"] {
+            assert!(game.try_make_move_uci(m));
+        }
+        let json = serde_json::to_string(&game.save_state()).unwrap();
+        let state: chess::GameState = serde_json::from_str(&json).unwrap();
+        assert!(chess::Game::load_state(&state).unwrap() == game);
+
+        // the same moves from a position where the first one can't be played
+        let json = json.replace("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR", "4k3/8/8/8/8/8/8/4K3");
+        let state: chess::GameState = serde_json::from_str(&json).unwrap();
+        assert_eq!(chess::Game::load_state(&state).err(), Some(chess::StateError::IllegalMove(0, chess::MoveError::EmptySquare)));
+    }
+
+    #[test]
+    fn legal_move_cache() {
+        let mut game = chess::Game::default();
+        game.set_legal_move_cache(true);
+        let mut fresh = chess::Game::default();
+        let matches = |game: &mut chess::Game, fresh: &mut chess::Game| -> bool {
+            game.get_all_legal_moves() == fresh.get_all_legal_moves()
+                && game.get_legal_moves(4, 1) == fresh.get_legal_moves(4, 1)
+                && game.get_legal_move_list(6, 0) == fresh.get_legal_move_list(6, 0)
         };
-        let c = piece_char(&piece);
-        screen.set_pixel(x, y, None, Some(fg), Some(c));
+        assert!(matches(&mut game, &mut fresh));
+
+        for m in ["e2e4", "e7e5", "d1h5", "b8c6", "f1c4", "g8f6"] {
+            assert!(game.try_make_move_uci(m));
+            assert!(fresh.try_make_move_uci(m));
+            assert!(matches(&mut game, &mut fresh));
+        }
+        // the position after h5xf7 is checkmate, which a stale list would miss
+        assert!(game.try_make_move_uci("h5f7"));
+        assert!(game.get_all_legal_moves().is_empty());
+        assert!(game.undo_move());
+        assert!(matches(&mut game, &mut fresh));
+        assert!(game.redo_move());
+        assert!(game.is_checkmate());
+        assert!(game.undo_move());
+
+        assert!(game.make_null_move());
+        assert!(fresh.make_null_move());
+        assert!(matches(&mut game, &mut fresh));
+        assert!(game.unmake_null_move());
+        assert!(fresh.unmake_null_move());
+        assert!(matches(&mut game, &mut fresh));
+
+        game.best_move(2);
+        assert!(matches(&mut game, &mut fresh));
+    }
+
+    #[test]
+    fn attack_heatmap() {
+        let game = chess::Game::default();
+        let white = game.attack_heatmap(chess::Color::White);
+        assert_eq!(white[2][5], 3);
+        assert_eq!(white[2][3], 2);
+        assert_eq!(white[0][1], 1);
+        assert_eq!(white[0][0], 0);
+        // the rook on a1 is stopped by the pawn on a2, so only the knight and the b2 pawn reach a3
+        assert_eq!(white[2][0], 2);
+        assert_eq!(white[3], [0; chess::BOARD_SIZE]);
+        assert_eq!(white[7], [0; chess::BOARD_SIZE]);
+
+        let black = game.attack_heatmap(chess::Color::Black);
+        assert_eq!(black[5][5], 3);
+        assert_eq!(black[2], [0; chess::BOARD_SIZE]);
+
+        // d5 contested by a pawn and a queen on both sides
+        let game = fen::parse("3qk3/8/4p3/8/4P3/8/8/3QK3 w - -").unwrap();
+        assert_eq!(game.attack_heatmap(chess::Color::White)[4][3], 2);
+        assert_eq!(game.attack_heatmap(chess::Color::Black)[4][3], 2);
+    }
+
+    #[test]
+    fn make_move_unchecked() {
+        let mut game = fen::parse("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 7 20").unwrap();
+        let before = fen::to_fen(&game);
+        let rook_move = game.make_move_unchecked(chess::Move::new(7, 0, 7, 6));
+        assert_eq!(rook_move.captured(), None);
+        assert_eq!(game.halfmove_clock(), 8);
+        assert!(!game.castling_rights().kingside(chess::Color::White));
+        assert!(game.castling_rights().kingside(chess::Color::Black));
+
+        let king_move = game.make_move_unchecked(chess::Move::new(4, 0, 3, 0));
+        // only the last move made can be taken back
+        assert!(!game.unmake_move(rook_move));
+        assert!(game.unmake_move(king_move));
+        assert!(game.make_null_move());
+        assert!(!game.unmake_move(rook_move));
+        assert!(game.unmake_null_move());
+        assert!(game.unmake_move(rook_move));
+        assert_eq!(fen::to_fen(&game), before);
+        assert!(game.history().is_empty());
+        assert!(!game.unmake_move(rook_move));
+    }
+
+    #[test]
+    fn all_moves_to() {
+        let mut game = chess::Game::default();
+        assert_eq!(game.all_moves_to(5, 2), vec![(6, 0), (5, 1)]);
+        assert_eq!(game.all_moves_to(4, 4), vec![]);
+        // a square only the opponent could move to
+        assert_eq!(game.all_moves_to(5, 5), vec![]);
+
+        let mut game = fen::parse("4r1k1/8/8/8/8/1N3N2/4B3/R3K3 w - -").unwrap();
+        assert_eq!(game.all_moves_to(3, 1), vec![(4, 0), (1, 2), (5, 2)]);
+        // the bishop on e2 is pinned to its king
+        assert_eq!(game.all_moves_to(3, 2), vec![]);
+        assert_eq!(game.all_moves_to(2, 0), vec![(0, 0), (1, 2)]);
+        for (x, y) in game.all_moves_to(3, 3) {
+            assert!(game.get_legal_moves(x, y).contains(&(3, 3)));
+        }
     }
 
-    fn draw_board(game: &chess::Game, x: usize, y: usize, screen: &mut graphics::Screen) -> () {
-        for dy in 0..8 {
-            for dx in 0..8 {
-                
-                let bg = if (dx + dy) % 2 == 0 {
-                    graphics::Color::new(196, 196, 196)
+    #[test]
+    fn undo_move() {
+        let mut game = chess::Game::default();
+        assert!(!game.undo_move());
+
+        assert!(game.try_make_move_uci("e2e4"));
+        assert!(game.try_make_move_uci("d7d5"));
+        assert!(game.try_make_move_uci("e4d5"));
+        assert_eq!(game.total_pieces(), 31);
+
+        assert!(game.undo_move());
+        assert_eq!(game.player_to_move(), chess::Color::White);
+        assert_eq!(game.total_pieces(), 32);
+        assert_eq!(game.get_piece(3, 4).unwrap().piece_color(), chess::Color::Black);
+        assert!(game.undo_move());
+        assert!(game.undo_move());
+        assert!(!game.undo_move());
+        assert_eq!(game.get_piece(4, 1).unwrap().piece_type(), chess::PieceType::Pawn);
+        assert!(game.get_piece(4, 3).is_none());
+
+        let mut game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "a1"),
+            (chess::PieceType::Pawn, chess::Color::White, "e7"),
+            (chess::PieceType::King, chess::Color::Black, "h8"),
+        ]).unwrap();
+        assert!(game.try_make_move_uci("e7e8"));
+        assert!(game.undo_move());
+        assert_eq!(game.player_to_move(), chess::Color::White);
+        assert_eq!(game.get_piece(4, 6).unwrap().piece_type(), chess::PieceType::Pawn);
+        assert!(game.get_piece(4, 7).is_none());
+    }
+
+    #[test]
+    fn history() {
+        let mut game = chess::Game::default();
+        assert!(game.history().is_empty());
+
+        assert!(game.try_make_move_uci("e2e4"));
+        assert!(game.try_make_move_uci("d7d5"));
+        assert!(game.try_make_move_uci("e4d5"));
+        assert_eq!(game.history().len(), 3);
+
+        let record = game.history()[1];
+        assert_eq!(record.moved().piece_color(), chess::Color::Black);
+        assert_eq!(record.from(), (3, 1));
+        assert_eq!(record.to(), (3, 3));
+        assert!(record.captured().is_none());
+
+        let record = game.history()[2];
+        assert_eq!(record.moved().piece_type(), chess::PieceType::Pawn);
+        assert_eq!(record.captured().unwrap().piece_color(), chess::Color::Black);
+        assert!(record.promotion().is_none());
+
+        assert!(game.undo_move());
+        assert_eq!(game.history().len(), 2);
+    }
+
+    #[test]
+    fn undo_to_ply_and_redo() {
+        let mut game = chess::Game::default();
+        for m in ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5"] {
+            assert!(game.try_make_move_uci(m));
+        }
+        let end = game.to_fen();
+
+        assert!(game.undo_to_ply(2));
+        assert_eq!(game.ply(), 2);
+        assert_eq!(game.history().len(), 2);
+        assert!(!game.undo_to_ply(4));
+
+        // trying out moves in between, as to_san and the search do, doesn't disturb the moves waiting to be redone
+        let m = game.get_all_legal_moves()[0];
+        assert!(notation::to_san(&mut game, m).is_some());
+        while game.redo_move() {}
+        assert_eq!(game.to_fen(), end);
+
+        assert!(game.undo_to_ply(0));
+        assert!(!game.undo_move());
+        assert!(game.redo_move());
+        assert!(game.try_make_move_uci("d7d5"));
+        assert!(!game.redo_move());
+        assert_eq!(game.ply(), 2);
+
+        let mut game = fen::parse("4k3/8/8/8/8/8/8/4K3 w - - 0 10").unwrap();
+        assert!(game.try_make_move_uci("e1e2"));
+        assert!(!game.undo_to_ply(0));
+        assert_eq!(game.ply(), 18);
+    }
+
+    #[test]
+    fn default_game() {
+        let game = chess::Game::default();
+        let expected = chess::Game::new(chess::Board::new(chess::BOARD_DEFAULT_SETUP));
+        assert!(game.same_position(&expected));
+    }
+
+    #[test]
+    fn kings_cannot_touch() {
+        let mut game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "e4"),
+            (chess::PieceType::King, chess::Color::Black, "e6"),
+        ]).unwrap();
+
+        let moves = game.get_legal_moves(4, 3);
+        assert_eq!(moves, HashSet::from([(3, 3), (5, 3), (3, 2), (4, 2), (5, 2)]));
+
+        assert!(game.try_make_move_uci("e4d4"));
+        let moves = game.get_legal_moves(4, 2);
+        assert!(!moves.contains(&(3, 3)));
+        assert!(!moves.contains(&(4, 3)));
+        assert!(moves.contains(&(5, 3)));
+    }
+
+    #[test]
+    fn evaluate_positional() {
+        let mut game = chess::Game::default();
+        assert_eq!(game.evaluate(), 0);
+        assert_eq!(game.evaluate_positional(), 0);
+
+        assert!(game.try_make_move_uci("e2e4"));
+        assert_eq!(game.evaluate(), 0);
+        assert_eq!(game.evaluate_positional(), 40);
+        assert!(game.try_make_move_uci("e7e5"));
+        assert_eq!(game.evaluate_positional(), 0);
+
+        let centralized = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "g1"),
+            (chess::PieceType::Knight, chess::Color::White, "d4"),
+            (chess::PieceType::King, chess::Color::Black, "g8"),
+            (chess::PieceType::Knight, chess::Color::Black, "a5"),
+        ]).unwrap();
+        assert_eq!(centralized.evaluate(), 0);
+        assert!(centralized.evaluate_positional() > 0);
+    }
+
+    #[test]
+    fn evaluate_mobility() {
+        let mut game = chess::Game::default();
+        assert_eq!(game.evaluate_mobility(), 0);
+
+        // the e-pawn frees the bishop and queen
+        assert!(game.try_make_move_uci("e2e4"));
+        assert!(game.evaluate_mobility() > 0);
+        assert!(game.try_make_move_uci("e7e5"));
+        assert_eq!(game.evaluate_mobility(), 0);
+
+        let mut cramped = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "a1"),
+            (chess::PieceType::Rook, chess::Color::White, "d4"),
+            (chess::PieceType::King, chess::Color::Black, "h8"),
+            (chess::PieceType::Rook, chess::Color::Black, "h7"),
+        ]).unwrap();
+        // a centralized rook has 14 moves, the black rook is hemmed in by its own king
+        assert_eq!(cramped.evaluate_mobility(), 4 * ((3 + 14) - (2 + 13)));
+
+        let mut context = search::SearchContext::new();
+        context.set_mobility(true);
+        assert!(context.best_move(&mut cramped, 2).is_some());
+    }
+
+    #[test]
+    fn all_piece_types_and_colors() {
+        for (i, piece_type) in chess::ALL_PIECE_TYPES.into_iter().enumerate() {
+            assert_eq!(piece_type as usize, i);
+        }
+        let game = chess::Game::default();
+        let counts: Vec<usize> = chess::ALL_PIECE_TYPES.iter().map(|&piece_type| game.count(piece_type, chess::Color::White)).collect();
+        assert_eq!(counts, vec![8, 2, 2, 2, 1, 1]);
+        assert_eq!(chess::ALL_COLORS.map(|color| color.opponent()), [chess::Color::Black, chess::Color::White]);
+    }
+
+    #[test]
+    fn material_balance() {
+        let material = chess::Game::default().material_balance();
+        assert_eq!(material.count(chess::PieceType::Pawn, chess::Color::White), 8);
+        assert_eq!(material.count(chess::PieceType::King, chess::Color::Black), 1);
+        assert_eq!(material.value(chess::Color::White), 8 * 100 + 2 * 320 + 2 * 330 + 2 * 500 + 900);
+        assert_eq!(material.balance(), 0);
+
+        let game = fen::parse("4k3/8/8/8/8/8/3PP3/2N1K3 b - -").unwrap();
+        let material = game.material_balance();
+        assert_eq!(material.count(chess::PieceType::Pawn, chess::Color::White), 2);
+        assert_eq!(material.count(chess::PieceType::Knight, chess::Color::White), 1);
+        assert_eq!(material.count(chess::PieceType::Pawn, chess::Color::Black), 0);
+        assert_eq!(material.balance(), 520);
+        assert_eq!(material.balance(), game.evaluate());
+    }
+
+    #[test]
+    fn king_catches_pawn() {
+        // the black king on e5 is just outside the square of the pawn on a5, until it's black to move
+        let game = fen::parse("8/8/8/P3k3/8/8/8/7K w - -").unwrap();
+        assert_eq!(game.king_catches_pawn(), Some(false));
+        let game = fen::parse("8/8/8/P3k3/8/8/8/7K b - -").unwrap();
+        assert_eq!(game.king_catches_pawn(), Some(true));
+        let game = fen::parse("8/8/8/P2k4/8/8/8/7K w - -").unwrap();
+        assert_eq!(game.king_catches_pawn(), Some(true));
+
+        // a pawn on its starting rank can step twice
+        let game = fen::parse("8/8/8/8/6k1/8/P7/7K w - -").unwrap();
+        assert_eq!(game.king_catches_pawn(), Some(false));
+        let game = fen::parse("7k/p7/8/8/5K2/8/8/8 b - -").unwrap();
+        assert_eq!(game.king_catches_pawn(), Some(true));
+        let game = fen::parse("7k/p7/8/8/6K1/8/8/8 b - -").unwrap();
+        assert_eq!(game.king_catches_pawn(), Some(false));
+
+        assert_eq!(chess::Game::default().king_catches_pawn(), None);
+        assert_eq!(fen::parse("4k3/8/8/8/8/8/3PP3/4K3 w - -").unwrap().king_catches_pawn(), None);
+        assert_eq!(fen::parse("4k3/8/8/8/8/8/3P4/2N1K3 w - -").unwrap().king_catches_pawn(), None);
+        assert_eq!(fen::parse("4k3/8/8/8/8/8/3P4/4K3 w - -").unwrap().king_catches_pawn(), Some(true));
+    }
+
+    #[test]
+    fn evaluate_rook_placement() {
+        assert_eq!(chess::Game::default().evaluate_rook_placement(), 0);
+
+        // White's rook is on an open file, Black's on a half-open one
+        let game = fen::parse("4k2r/8/8/8/8/8/7P/R3K3 w - -").unwrap();
+        assert_eq!(game.evaluate_rook_placement(), 25 - 10);
+        let weights = eval::EvalWeights { rook_open_file: 40, rook_half_open_file: 0, ..Default::default() };
+        assert_eq!(game.evaluate_rook_placement_with(&weights), 40);
+
+        // a rook on the seventh, on a file its own pawn closes
+        let game = fen::parse("4k3/R7/8/8/8/8/P7/4K3 b - -").unwrap();
+        assert_eq!(game.evaluate_rook_placement(), 20);
+        assert_eq!(game.mirrored().evaluate_rook_placement(), -20);
+    }
+
+    #[test]
+    fn evaluate_bishop_pair() {
+        assert_eq!(chess::Game::default().evaluate_bishop_pair(), 0);
+
+        // Black has traded a bishop for a knight
+        let game = fen::parse("rn1qkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKB1R w KQkq -").unwrap();
+        assert_eq!(game.evaluate_bishop_pair(), 40);
+        assert_eq!(game.mirrored().evaluate_bishop_pair(), -40);
+        let weights = eval::EvalWeights { bishop_pair: 50, ..Default::default() };
+        assert_eq!(game.evaluate_bishop_pair_with(&weights), 50);
+
+        let game = fen::parse("2b1kb2/8/8/8/8/8/8/2B1K3 w - -").unwrap();
+        assert_eq!(game.evaluate_bishop_pair(), -40);
+    }
+
+    #[test]
+    fn captured_pieces() {
+        let mut game = chess::Game::default();
+        for m in ["e2e4", "d7d5", "e4d5", "d8d5", "b1c3", "d5a2", "a1a2"] {
+            assert!(game.try_make_move_uci(m));
+        }
+        let pawn = |color| chess::Piece::new(chess::PieceType::Pawn, color);
+        assert_eq!(game.captured_pieces(chess::Color::White), vec![pawn(chess::Color::White), pawn(chess::Color::White)]);
+        assert_eq!(game.captured_pieces(chess::Color::Black), vec![pawn(chess::Color::Black), chess::Piece::new(chess::PieceType::Queen, chess::Color::Black)]);
+
+        assert!(game.undo_move());
+        assert_eq!(game.captured_pieces(chess::Color::Black), vec![pawn(chess::Color::Black)]);
+        assert!(chess::Game::default().captured_pieces(chess::Color::White).is_empty());
+    }
+
+    #[test]
+    fn zobrist_hash() {
+        let mut game = chess::Game::default();
+        let start = game.zobrist_hash();
+
+        assert!(game.try_make_move_uci("g1f3"));
+        assert!(game.try_make_move_uci("g8f6"));
+        assert!(game.try_make_move_uci("f3g1"));
+        assert_ne!(game.zobrist_hash(), start);
+        assert!(game.try_make_move_uci("f6g8"));
+        assert_eq!(game.zobrist_hash(), start);
+    }
+
+    #[test]
+    fn best_move_with_transpositions() {
+        let mut game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "g1"),
+            (chess::PieceType::Rook, chess::Color::White, "a1"),
+            (chess::PieceType::Pawn, chess::Color::White, "f2"),
+            (chess::PieceType::Pawn, chess::Color::White, "g2"),
+            (chess::PieceType::Pawn, chess::Color::White, "h2"),
+            (chess::PieceType::King, chess::Color::Black, "g8"),
+            (chess::PieceType::Pawn, chess::Color::Black, "f7"),
+            (chess::PieceType::Pawn, chess::Color::Black, "g7"),
+            (chess::PieceType::Pawn, chess::Color::Black, "h7"),
+        ]).unwrap();
+
+        let mut context = search::SearchContext::new();
+        let mate = chess::Move::new(0, 0, 0, 7);
+        assert_eq!(context.best_move(&mut game, 3), Some(mate));
+        assert_eq!(context.best_move(&mut game, 3), Some(mate));
+        assert_eq!(game.best_move(2), Some(mate));
+        assert!(game.history().is_empty());
+    }
+
+    #[test]
+    fn hint() {
+        // black to move has a back rank mate
+        let mut game = fen::parse("r5k1/5ppp/8/8/8/8/5PPP/6K1 b - -").unwrap();
+        let mate = notation::parse_uci(&game, "a8a1").unwrap();
+        assert_eq!(game.hint(2), Some(mate));
+        assert_eq!(game.hint(100), Some(mate));
+        assert!(game.history().is_empty());
+    }
+
+    #[test]
+    fn best_move_timed() {
+        let mut game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "g1"),
+            (chess::PieceType::Rook, chess::Color::White, "a1"),
+            (chess::PieceType::Pawn, chess::Color::White, "f2"),
+            (chess::PieceType::Pawn, chess::Color::White, "g2"),
+            (chess::PieceType::Pawn, chess::Color::White, "h2"),
+            (chess::PieceType::King, chess::Color::Black, "g8"),
+            (chess::PieceType::Pawn, chess::Color::Black, "f7"),
+            (chess::PieceType::Pawn, chess::Color::Black, "g7"),
+            (chess::PieceType::Pawn, chess::Color::Black, "h7"),
+        ]).unwrap();
+
+        let start = std::time::Instant::now();
+        assert_eq!(game.best_move_timed(300), Some(chess::Move::new(0, 0, 0, 7)));
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+        assert!(game.history().is_empty());
+
+        // without any time there's still a legal move to play
+        let m = game.best_move_timed(0).unwrap();
+        assert!(game.get_all_legal_moves().contains(&m));
+    }
+
+    #[test]
+    fn best_move_pv() {
+        let mut game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "g1"),
+            (chess::PieceType::Queen, chess::Color::White, "d1"),
+            (chess::PieceType::Pawn, chess::Color::White, "g2"),
+            (chess::PieceType::King, chess::Color::Black, "g8"),
+            (chess::PieceType::Rook, chess::Color::Black, "d8"),
+            (chess::PieceType::Pawn, chess::Color::Black, "g7"),
+        ]).unwrap();
+
+        let (best, pv) = game.best_move_pv(3);
+        assert!(!pv.is_empty() && pv.len() <= 3);
+        assert_eq!(best, pv.first().copied());
+        assert!(game.history().is_empty());
+
+        // every move of the line can be played in turn
+        for &m in &pv {
+            assert!(game.get_all_legal_moves().contains(&m));
+            let (from, to) = (m.from(), m.to());
+            assert!(game.try_make_move(from.0, from.1, to.0, to.1));
+        }
+
+        let mut start = chess::Game::default();
+        let (best, pv) = start.best_move_pv(3);
+        assert_eq!(pv, best.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn ordered_moves() {
+        // the pawn and the knight can both take the queen, the rook can take a pawn
+        let mut game = fen::parse("4k3/8/8/3q3p/4P3/2N5/8/4K2R w - - 0 1").unwrap();
+        let moves: Vec<String> = game.get_ordered_moves().into_iter()
+            .map(|m| notation::to_san(&mut game, m).unwrap())
+            .collect();
+        assert_eq!(moves[..3], ["exd5", "Nxd5", "Rxh5"]);
+        assert!(moves[3..].iter().all(|san| !san.contains('x')));
+        assert_eq!(moves.len(), game.get_all_legal_moves().len());
+    }
+
+    #[test]
+    fn check_extensions() {
+        // a mate in three, five plies deep, found searching only three plies at a time
+        let mut game = fen::parse("r4b1k/6pp/8/6N1/2Q5/8/8/6K1 w - - 0 1").unwrap();
+        let mut played = Vec::new();
+        while !game.is_checkmate() && played.len() < 7 {
+            let depth = if game.player_to_move() == chess::Color::White { 3 } else { 1 };
+            let m = game.best_move(depth).unwrap();
+            played.push(notation::to_san(&mut game, m).unwrap());
+            assert!(game.try_make_move(m.from().0, m.from().1, m.to().0, m.to().1));
+        }
+        assert_eq!(played, ["Nf7+", "Kg8", "Nh6+", "Kh8", "Qg8#"]);
+    }
+
+    #[test]
+    fn null_move() {
+        let mut game = chess::Game::default();
+        assert!(!game.unmake_null_move());
+        assert!(game.make_null_move());
+        assert_eq!(game.player_to_move(), chess::Color::Black);
+        assert_eq!(game.to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1");
+        assert!(game.unmake_null_move());
+        assert!(game == chess::Game::default());
+
+        // passing after a double push gives up the en passant square
+        assert!(game.try_make_move_uci("e2e4"));
+        assert!(game.make_null_move());
+        assert_eq!(game.to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2");
+        assert!(game.unmake_null_move());
+        assert_eq!(game.to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+
+        let mut check = fen::parse("4k3/8/8/8/8/8/8/4R1K1 b - -").unwrap();
+        assert!(!check.make_null_move());
+
+        // moves made before a null move stay put until it's taken back
+        let mut game = chess::Game::default();
+        for m in ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8"] {
+            assert!(game.try_make_move_uci(m));
+        }
+        assert!(game.is_threefold_repetition());
+        assert!(game.make_null_move());
+        let fen = game.to_fen();
+        assert!(!game.undo_move());
+        assert!(!game.undo_to_ply(4));
+        assert!(!game.is_threefold_repetition());
+        assert_eq!(game.repetition_count(), 1);
+        assert_eq!(game.to_fen(), fen);
+        assert!(game.unmake_null_move());
+        assert!(game.is_threefold_repetition());
+        assert!(game.undo_move());
+        assert_eq!(game.ply(), 7);
+        assert!(game.make_null_move());
+        assert!(!game.redo_move());
+        assert!(game.unmake_null_move());
+        assert!(game.redo_move());
+        assert_eq!(game.to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 8 5");
+
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+        let mut game = fen::parse(fen).unwrap();
+        let mut context = search::SearchContext::new();
+        context.set_null_move_pruning(true);
+        assert_eq!(context.best_move(&mut game, 4), Some(chess::Move::new(5, 2, 5, 6)));
+        assert_eq!(game.to_fen(), fen);
+    }
+
+    #[test]
+    fn quiescence() {
+        let mut game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "g1"),
+            (chess::PieceType::Queen, chess::Color::White, "d1"),
+            (chess::PieceType::King, chess::Color::Black, "g8"),
+            (chess::PieceType::Pawn, chess::Color::Black, "d5"),
+            (chess::PieceType::Pawn, chess::Color::Black, "e6"),
+        ]).unwrap();
+
+        // taking the pawn looks good at depth 1 until the recapture is seen
+        let best = game.best_move(1).unwrap();
+        assert_ne!(best, chess::Move::new(3, 0, 3, 4));
+    }
+
+    #[test]
+    fn search_eval_weights() {
+        let mut game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "h1"),
+            (chess::PieceType::Queen, chess::Color::White, "d1"),
+            (chess::PieceType::King, chess::Color::Black, "g8"),
+            (chess::PieceType::Rook, chess::Color::Black, "b3"),
+            (chess::PieceType::Bishop, chess::Color::Black, "d4"),
+            (chess::PieceType::Bishop, chess::Color::Black, "h7"),
+        ]).unwrap();
+        let take_rook = chess::Move::new(3, 0, 1, 2);
+        let take_bishop = chess::Move::new(3, 0, 3, 3);
+
+        let mut context = search::SearchContext::new();
+        assert_eq!(context.best_move(&mut game, 1), Some(take_rook));
+
+        // breaking up the bishop pair is worth more than the rook once it's weighted that heavily
+        context.set_weights(eval::EvalWeights { bishop_pair: 1000, ..Default::default() });
+        assert_eq!(context.best_move(&mut game, 1), Some(take_bishop));
+    }
+
+    #[test]
+    fn legal_captures() {
+        let mut game = chess::Game::default();
+        assert!(game.get_all_legal_captures().is_empty());
+
+        assert!(game.try_make_move_uci("e2e4"));
+        assert!(game.try_make_move_uci("d7d5"));
+        assert_eq!(game.get_legal_captures(4, 3), HashSet::from([(3, 4)]));
+        assert_eq!(game.get_all_legal_captures(), vec![chess::Move::new(4, 3, 3, 4)]);
+
+        // the pinned knight can't take the bishop
+        let mut game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "e1"),
+            (chess::PieceType::Knight, chess::Color::White, "e2"),
+            (chess::PieceType::King, chess::Color::Black, "a8"),
+            (chess::PieceType::Rook, chess::Color::Black, "e8"),
+            (chess::PieceType::Bishop, chess::Color::Black, "d4"),
+        ]).unwrap();
+        assert!(game.get_legal_captures(4, 1).is_empty());
+        assert_eq!(game.get_all_legal_captures(), Vec::new());
+    }
+
+    #[test]
+    fn en_passant_capture() {
+        let mut game = chess::Game::default();
+        for uci in ["e2e4", "a7a6", "e4e5", "d7d5"] {
+            assert!(game.try_make_move_uci(uci));
+        }
+        let before = fen::to_fen(&game);
+        let capture = chess::Move::new(4, 4, 3, 5);
+        assert_eq!(game.get_legal_captures(4, 4), HashSet::from([(3, 5)]));
+        assert!(game.get_all_legal_captures().contains(&capture));
+        assert_eq!(notation::to_san(&mut game, capture).as_deref(), Some("exd6"));
+
+        assert!(game.try_make_move_uci("e5d6"));
+        assert_eq!(game.captured_pieces(chess::Color::Black), vec![chess::Piece::new(chess::PieceType::Pawn, chess::Color::Black)]);
+        assert_eq!(fen::to_fen(&game), "rnbqkbnr/1pp1pppp/p2P4/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3");
+
+        assert!(game.undo_move());
+        assert_eq!(fen::to_fen(&game), before);
+
+        // the chance is gone once another move has been played
+        assert!(game.try_make_move_uci("b1c3"));
+        assert!(game.try_make_move_uci("a6a5"));
+        assert!(game.get_legal_captures(4, 4).is_empty());
+    }
+
+    #[test]
+    fn draw_board_theme() {
+        let theme = render::BoardTheme {
+            dark_square: graphics::Color::new(0, 0, 128),
+            glyphs: |piece| match piece.piece_color() {
+                chess::Color::White => 'W',
+                chess::Color::Black => 'B',
+            },
+            ..Default::default()
+        };
+
+        let mut game = chess::Game::default();
+        let mut screen = graphics::Screen::new(8, 8);
+        render::draw_board(&game, 0, 0, &mut screen, &theme);
+        let (bg, _, c) = screen.get_pixel(0, 0);
+        assert_eq!((bg, c), (theme.dark_square, 'W'));
+        let (bg, _, c) = screen.get_pixel(1, 7);
+        assert_eq!((bg, c), (theme.dark_square, 'B'));
+        let (bg, _, c) = screen.get_pixel(1, 0);
+        assert_eq!((bg, c), (theme.light_square, 'W'));
+        for y in 0..chess::BOARD_SIZE {
+            for x in 0..chess::BOARD_SIZE {
+                let (bg, fg, _) = screen.get_pixel(x, y);
+                let expected = if (x + y).is_multiple_of(2) { theme.dark_square } else { theme.light_square };
+                assert_eq!(bg, expected);
+                if game.get_piece(x, y).is_some() {
+                    assert_eq!(fg, expected.readable_text_color());
+                }
+            }
+        }
+
+        assert!(game.try_make_move_san("f3"));
+        assert!(game.try_make_move_san("e5"));
+        assert!(game.try_make_move_san("g4"));
+        assert!(game.try_make_move_san("Qh4"));
+        render::draw_board(&game, 0, 0, &mut screen, &theme);
+        let (bg, _, _) = screen.get_pixel(4, 0);
+        assert_eq!(bg, theme.check);
+    }
+
+    #[test]
+    fn draw_moves_and_pieces() {
+        let theme = render::BoardTheme::default();
+        let mut game = chess::Game::default();
+        let mut screen = graphics::Screen::new(10, 10);
+
+        let moves = game.get_legal_moves(6, 0);
+        render::draw_moves(&moves, 1, 1, &mut screen, &theme);
+        for (x, y) in [(6, 3), (8, 3)] {
+            assert_eq!(screen.get_pixel(x, y).0, theme.move_dot);
+        }
+        assert_ne!(screen.get_pixel(7, 3).0, theme.move_dot);
+
+        let knight = game.get_piece(6, 0).unwrap();
+        render::draw_piece(&knight, 0, 0, &mut screen, &theme);
+        assert_eq!(screen.get_pixel(0, 0).2, '♘');
+    }
+
+    #[test]
+    fn move_counters() {
+        let mut game = chess::Game::default();
+        assert_eq!((game.fullmove_number(), game.ply()), (1, 0));
+
+        assert!(game.try_make_move_uci("e2e4"));
+        assert_eq!((game.fullmove_number(), game.ply()), (1, 1));
+        assert!(game.try_make_move_uci("e7e5"));
+        assert_eq!((game.fullmove_number(), game.ply()), (2, 2));
+        assert!(game.try_make_move_uci("g1f3"));
+        assert_eq!((game.fullmove_number(), game.ply()), (2, 3));
+
+        game.perft(3);
+        game.best_move(2);
+        assert_eq!((game.fullmove_number(), game.ply()), (2, 3));
+
+        assert!(game.undo_move());
+        assert!(game.undo_move());
+        assert_eq!((game.fullmove_number(), game.ply()), (1, 1));
+    }
+
+    #[test]
+    fn reset() {
+        let mut game = chess::Game::default();
+        assert!(game.try_make_move_uci("e2e4"));
+        assert!(game.try_make_move_uci("d7d5"));
+        assert!(game.try_make_move_uci("e4d5"));
+
+        game.reset();
+        assert_eq!(game.player_to_move(), chess::Color::White);
+        assert!(game.history().is_empty());
+        assert_eq!((game.fullmove_number(), game.ply()), (1, 0));
+        assert_eq!(game.total_pieces(), 32);
+        assert_eq!(game.zobrist_hash(), chess::Game::default().zobrist_hash());
+    }
+
+    #[test]
+    fn board_index() {
+        let mut board = chess::Board::default();
+        assert_eq!(board[(4, 0)].unwrap().piece_type(), chess::PieceType::King);
+        assert_eq!(board[(3, 7)].unwrap().piece_type(), chess::PieceType::Queen);
+        assert!(board[(4, 3)].is_none());
+
+        board[(4, 3)] = board[(4, 1)].take();
+        let game = chess::Game::new(board);
+        assert!(game.get_piece(4, 1).is_none());
+        assert_eq!(game.get_piece(4, 3).unwrap().piece_type(), chess::PieceType::Pawn);
+        assert_eq!(game.board()[(4, 3)].unwrap().piece_color(), chess::Color::White);
+    }
+
+    #[test]
+    fn same_position() {
+        let mut game = chess::Game::default();
+        let start = chess::Game::default();
+        assert!(game.board() == start.board());
+        assert_eq!(game.get_piece(0, 0), Some(chess::Piece::new(chess::PieceType::Rook, chess::Color::White)));
+
+        assert!(game.try_make_move_uci("g1f3"));
+        assert!(!game.same_position(&start));
+        assert!(game.try_make_move_uci("g8f6"));
+        assert!(game.try_make_move_uci("f3g1"));
+        assert!(!game.same_position(&start));
+        assert!(game.try_make_move_uci("f6g8"));
+        assert!(game.same_position(&start));
+        assert_ne!(game.ply(), start.ply());
+
+        // only the castling rights differ
+        let castling = fen::parse("r3k2r/8/8/8/8/8/8/R3K2R w KQkq -").unwrap();
+        let no_castling = fen::parse("r3k2r/8/8/8/8/8/8/R3K2R w - -").unwrap();
+        assert!(!castling.same_position(&no_castling));
+
+        // only the en passant square differs, after a double push and after two single steps
+        let mut double_push = fen::parse("4k3/8/8/8/8/8/4P3/4K3 w - -").unwrap();
+        assert!(double_push.try_make_move_uci("e2e4"));
+        let mut single_steps = fen::parse("4k3/8/8/8/8/8/4P3/4K3 w - -").unwrap();
+        for m in ["e2e3", "e8d8", "e3e4", "d8d7", "e1d1", "d7e8", "d1e1"] {
+            assert!(single_steps.try_make_move_uci(m));
+        }
+        assert!(!double_push.same_position(&single_steps));
+        assert!(double_push.try_make_move_uci("e8d8"));
+        assert!(single_steps.try_make_move_uci("e8d8"));
+        assert!(double_push.same_position(&single_steps));
+    }
+
+    #[test]
+    fn position_key() {
+        let mut game = chess::Game::default();
+        let mut seen: HashMap<chess::PositionKey, usize> = HashMap::new();
+        *seen.entry(game.position_key()).or_default() += 1;
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8"] {
+            assert!(game.try_make_move_uci(uci));
+            *seen.entry(game.position_key()).or_default() += 1;
+        }
+        assert_eq!(seen.len(), 4);
+        assert_eq!(seen[&chess::Game::default().position_key()], 3);
+
+        // only the castling rights differ
+        let castling = fen::parse("r3k2r/8/8/8/8/8/8/R3K2R w KQkq -").unwrap();
+        let no_castling = fen::parse("r3k2r/8/8/8/8/8/8/R3K2R w - -").unwrap();
+        assert_ne!(castling.position_key(), no_castling.position_key());
+
+        // the rooks going out and back lose the rights, so the position doesn't repeat
+        let mut game = fen::parse("r3k2r/8/8/8/8/8/8/R3K2R w KQkq -").unwrap();
+        for m in ["h1g1", "h8g8", "g1h1", "g8h8"] {
+            assert!(game.try_make_move_uci(m));
+        }
+        assert_eq!(game.repetition_count(), 1);
+        assert_eq!(game.position_key(), fen::parse("r3k2r/8/8/8/8/8/8/R3K2R w Qq -").unwrap().position_key());
+    }
+
+    #[test]
+    fn draw_text() {
+        let mut screen = graphics::Screen::new(8, 2);
+        let red = graphics::Color::new(255, 0, 0);
+        screen.draw_text(2, 1, "Checkmate!", Some(red), None);
+
+        let row: String = (0..8).map(|x| screen.get_pixel(x, 1).2).collect();
+        assert_eq!(row, "  Checkm");
+        assert_eq!(screen.get_pixel(2, 1).1, red);
+        assert_eq!(screen.get_pixel(0, 1).1, graphics::Color::new(255, 255, 255));
+
+        screen.draw_text(0, 2, "off screen", None, None);
+        screen.draw_text(9, 0, "off screen", None, None);
+    }
+
+    #[test]
+    fn draw_text_wrapped() {
+        let mut screen = graphics::Screen::new(12, 6);
+        let row = |screen: &graphics::Screen, y: usize| -> String { (0..12).map(|x| screen.get_pixel(x, y).2).collect() };
+
+        assert_eq!(screen.draw_text_wrapped(1, 0, 10, "White wins by checkmate", None, None), 3);
+        assert_eq!(row(&screen, 0), " White wins ");
+        assert_eq!(row(&screen, 1), " by         ");
+        assert_eq!(row(&screen, 2), " checkmate  ");
+
+        let mut screen = graphics::Screen::new(12, 6);
+        assert_eq!(screen.draw_text_wrapped(0, 0, 4, "a stalemate", None, None), 4);
+        assert_eq!(row(&screen, 0), "a           ");
+        assert_eq!(row(&screen, 1), "stal        ");
+        assert_eq!(row(&screen, 2), "emat        ");
+        assert_eq!(row(&screen, 3), "e           ");
+
+        assert_eq!(screen.draw_text_wrapped(0, 5, 12, "", None, None), 0);
+        assert_eq!(screen.draw_text_wrapped(0, 5, 3, "past the bottom", None, None), 5);
+    }
+
+    #[test]
+    fn render_cell_width() {
+        let mut screen = graphics::Screen::new(2, 1);
+        screen.draw_text(0, 0, "ab", None, None);
+        let strip = |render: String| -> String {
+            let mut text = String::new();
+            let mut chars = render.chars();
+            while let Some(c) = chars.next() {
+                if c == '\x1B' {
+                    chars.by_ref().find(|&c| c == 'm');
                 } else {
-                    graphics::Color::new(32, 32, 32)
-                };
-                screen.set_pixel(x + dx, y + dy, Some(bg), None, None);
-                
-                if let Some(piece) = game.get_piece(dx, dy) {
-                    draw_piece(&piece, x + dx, y + dy, screen);
+                    text.push(c);
+                }
+            }
+            text
+        };
+
+        assert_eq!(strip(screen.render(false, false)), " a  b \n");
+        assert_eq!(strip(screen.render_with_cell_width(false, false, 3)), " a  b \n");
+        assert_eq!(strip(screen.render_with_cell_width(false, false, 2)), "a b \n");
+        assert_eq!(strip(screen.render_with_cell_width(true, false, 1)), "ba\n");
+        assert_eq!(strip(screen.render_with_cell_width(false, false, 0)), "ab\n");
+    }
+
+    #[test]
+    fn render_half_block() {
+        let mut screen = graphics::Screen::new(2, 3);
+        screen.set_pixel(0, 0, Some(graphics::Color::new(1, 2, 3)), None, Some('x'));
+        screen.set_pixel(0, 1, Some(graphics::Color::new(4, 5, 6)), None, None);
+        screen.set_pixel(1, 2, Some(graphics::Color::new(7, 8, 9)), None, None);
+
+        let render = screen.render_half_block(false, false);
+        assert_eq!(render.matches('\n').count(), 2);
+        assert!(render.starts_with("\x1B[38;2;1;2;3m\x1B[48;2;4;5;6m▀"));
+        assert!(!render.contains('x'));
+        assert!(render.contains("\x1B[38;2;7;8;9m\x1B[48;2;0;0;0m▀\n"));
+
+        // flipped vertically the odd row is paired with the one above it and the first row is left over
+        let render = screen.render_half_block(false, true);
+        assert!(render.starts_with("\x1B[38;2;0;0;0m\x1B[48;2;4;5;6m▀\x1B[38;2;7;8;9m"));
+        assert!(render.contains("\n\x1B[38;2;1;2;3m\x1B[48;2;0;0;0m▀"));
+    }
+
+    #[test]
+    fn crop() {
+        let mut screen = graphics::Screen::new(4, 3);
+        screen.draw_text(0, 0, "abcd", None, None);
+        screen.draw_text(0, 1, "efgh", Some(graphics::Color::new(255, 0, 0)), None);
+        screen.draw_text(0, 2, "ijkl", None, None);
+
+        let cropped = screen.crop(1, 1, 2, 2);
+        assert_eq!((cropped.width(), cropped.height()), (2, 2));
+        assert_eq!(cropped.get_pixel(0, 0).2, 'f');
+        assert_eq!(cropped.get_pixel(1, 1).2, 'k');
+        assert_eq!(cropped.get_pixel(0, 0).1, graphics::Color::new(255, 0, 0));
+
+        // clamped to the source
+        let cropped = screen.crop(3, 1, 5, 5);
+        assert_eq!((cropped.width(), cropped.height()), (1, 2));
+        assert_eq!(cropped.get_pixel(0, 1).2, 'l');
+        let cropped = screen.crop(9, 9, 2, 2);
+        assert_eq!((cropped.width(), cropped.height()), (0, 0));
+    }
+
+    #[test]
+    fn flip_and_rotate() {
+        let mut screen = graphics::Screen::new(3, 2);
+        screen.draw_text(0, 0, "abc", None, Some(graphics::Color::new(255, 255, 255)));
+        screen.draw_text(0, 1, "def", None, None);
+        let rows = |screen: &graphics::Screen| -> Vec<String> {
+            (0..screen.height()).map(|y| (0..screen.width()).map(|x| screen.get_pixel(x, y).2).collect()).collect()
+        };
+
+        assert_eq!(rows(&screen.flipped_horizontal()), vec!["cba", "fed"]);
+        assert_eq!(rows(&screen.flipped_vertical()), vec!["def", "abc"]);
+        let rotated = screen.rotated_180();
+        assert_eq!(rows(&rotated), vec!["fed", "cba"]);
+        assert_eq!(rotated.get_pixel(2, 1).0, graphics::Color::new(255, 255, 255));
+        assert_eq!(rotated.get_pixel(2, 0).0, graphics::Color::new(0, 0, 0));
+        assert_eq!(rotated.render(false, false), screen.render(true, true));
+    }
+
+    #[test]
+    fn flood_fill() {
+        let wall = graphics::Color::new(255, 255, 255);
+        let paint = graphics::Color::new(0, 0, 255);
+        let mut screen = graphics::Screen::new(5, 4);
+        // a wall down column 2 with a gap on the bottom row, and a walled off corner cell
+        for y in 0..3 {
+            screen.set_pixel(2, y, Some(wall), None, None);
+        }
+        screen.set_pixel(3, 0, Some(wall), None, None);
+        screen.set_pixel(4, 1, Some(wall), None, None);
+
+        screen.flood_fill(0, 0, paint);
+        assert_eq!(screen.get_pixel(1, 2).0, paint);
+        assert_eq!(screen.get_pixel(3, 1).0, paint);
+        assert_eq!(screen.get_pixel(2, 3).0, paint);
+        assert_eq!(screen.get_pixel(2, 1).0, wall);
+        assert_eq!(screen.get_pixel(4, 0).0, graphics::Color::new(0, 0, 0));
+
+        // filling with the same color or from off screen does nothing
+        screen.flood_fill(0, 0, paint);
+        screen.flood_fill(9, 9, wall);
+        assert_eq!(screen.get_pixel(0, 0).0, paint);
+
+        let mut large = graphics::Screen::new(500, 500);
+        large.flood_fill(250, 250, paint);
+        assert_eq!(large.get_pixel(0, 499).0, paint);
+    }
+
+    #[test]
+    fn clear_region() {
+        let mut screen = graphics::Screen::new(4, 4);
+        screen.clear(None, None, Some('.'));
+        let blue = graphics::Color::new(0, 0, 255);
+        screen.clear_region(2, 1, 5, 2, Some(blue), None, None);
+        screen.clear_region(0, 3, 1, 9, None, None, Some('#'));
+
+        let rows: Vec<String> = (0..4).map(|y| (0..4).map(|x| screen.get_pixel(x, y).2).collect()).collect();
+        assert_eq!(rows, vec!["....", "....", "....", "#..."]);
+        for y in 0..4 {
+            for x in 0..4 {
+                let expected = if x >= 2 && (1..3).contains(&y) { blue } else { graphics::Color::new(0, 0, 0) };
+                assert_eq!(screen.get_pixel(x, y).0, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn render_to() {
+        let mut screen = graphics::Screen::new(3, 2);
+        screen.draw_text(0, 1, "abc", Some(graphics::Color::new(1, 2, 3)), None);
+
+        let mut out: Vec<u8> = Vec::new();
+        screen.render_to(&mut out, true, true).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), screen.render(true, true));
+    }
+
+    #[test]
+    fn present() {
+        let mut screen = graphics::Screen::new(3, 2);
+        screen.draw_text(0, 1, "abc", Some(graphics::Color::new(1, 2, 3)), None);
+
+        let mut out: Vec<u8> = Vec::new();
+        screen.present_to(&mut out, false, true).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), format!("\x1B[?25l\x1B[H\x1B[2J{}\x1B[?25h", screen.render(false, true)));
+
+        // only the changed cell is drawn, at its place on the flipped screen
+        let mut next = graphics::Screen::new(3, 2);
+        next.draw_text(0, 1, "abd", Some(graphics::Color::new(1, 2, 3)), None);
+        let mut out: Vec<u8> = Vec::new();
+        next.present_diff_to(&mut out, &screen, false, true).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("\x1B[?25l\x1B[1;7H"));
+        assert!(out.contains(" d "));
+        assert!(!out.contains('a'));
+        assert!(!out.contains("\x1B[2J"));
+        assert!(out.ends_with("\x1B[0m\x1B[3;1H\x1B[?25h"));
+
+        let mut out: Vec<u8> = Vec::new();
+        graphics::Screen::new(2, 2).present_diff_to(&mut out, &screen, false, false).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("\x1B[2J"));
+    }
+
+    #[test]
+    fn terminal_modes() {
+        let mut out: Vec<u8> = Vec::new();
+        graphics::enter_alt_screen(&mut out).unwrap();
+        graphics::hide_cursor(&mut out).unwrap();
+        graphics::show_cursor(&mut out).unwrap();
+        graphics::leave_alt_screen(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\x1B[?1049h\x1B[?25l\x1B[?25h\x1B[?1049l");
+    }
+
+    #[test]
+    fn checked_pixels() {
+        let red = graphics::Color::new(200, 0, 0);
+        let mut screen = graphics::Screen::new(3, 2);
+        assert!(screen.try_set_pixel(2, 1, Some(red), None, Some('x')));
+        assert!(!screen.try_set_pixel(3, 1, Some(red), None, Some('x')));
+        assert!(!screen.try_set_pixel(0, 2, Some(red), None, Some('x')));
+        assert_eq!(screen.try_get_pixel(2, 1), Some(screen.get_pixel(2, 1)));
+        assert_eq!(screen.try_get_pixel(2, 1).map(|(bg, _, c)| (bg, c)), Some((red, 'x')));
+        assert_eq!(screen.try_get_pixel(3, 0), None);
+        assert_eq!(screen.try_get_pixel(0, 2), None);
+    }
+
+    #[test]
+    fn blit() {
+        let red = graphics::Color::new(200, 0, 0);
+        let blue = graphics::Color::new(0, 0, 200);
+        let mut screen = graphics::Screen::new(4, 3);
+        screen.clear(Some(blue), None, Some('.'));
+
+        let mut sprite = graphics::Screen::new_transparent(2, 2);
+        sprite.set_pixel(0, 0, Some(red), None, None);
+        sprite.set_pixel(1, 1, None, Some(red), Some('x'));
+        screen.blit(&sprite, 3, 1);
+        screen.blit(&sprite, 0, 0);
+        assert_eq!(screen.get_pixel(0, 0), (red, graphics::Color::new(255, 255, 255), '.'));
+        assert_eq!(screen.get_pixel(1, 1), (blue, red, 'x'));
+        assert_eq!(screen.get_pixel(3, 1).0, red);
+        assert_eq!(screen.get_pixel(1, 0).0, blue);
+    }
+
+    #[test]
+    fn screen_eq() {
+        let red = graphics::Color::new(200, 0, 0);
+        let mut layers = graphics::Layers::new(3, 2);
+        layers.add_layer("board").clear(None, None, Some('o'));
+        layers.add_layer("text").draw_text(1, 1, "x", Some(red), None);
+
+        let mut screen = graphics::Screen::new(3, 2);
+        screen.clear(None, None, Some('o'));
+        screen.set_pixel(1, 1, None, Some(red), Some('x'));
+        assert_eq!(layers.compose(), screen);
+
+        screen.set_pixel(0, 0, Some(red), None, None);
+        assert_ne!(layers.compose(), screen);
+        assert_ne!(graphics::Screen::new(3, 2), graphics::Screen::new(2, 3));
+
+        let theme = render::BoardTheme::default();
+        let mut game = chess::Game::default();
+        let mut before = graphics::Screen::new(8, 8);
+        render::draw_board(&game, 0, 0, &mut before, &theme);
+        game.try_play_move(chess::Move::new(4, 1, 4, 3)).unwrap();
+        let mut after = graphics::Screen::new(8, 8);
+        render::draw_board(&game, 0, 0, &mut after, &theme);
+        assert_ne!(before, after);
+        game.undo_move();
+        let mut undone = graphics::Screen::new(8, 8);
+        render::draw_board(&game, 0, 0, &mut undone, &theme);
+        assert_eq!(before, undone);
+    }
+
+    #[test]
+    fn layers() {
+        let red = graphics::Color::new(200, 0, 0);
+        let blue = graphics::Color::new(0, 0, 200);
+        let mut layers = graphics::Layers::new(3, 1);
+        layers.add_layer("board").clear(Some(blue), None, Some('o'));
+        layers.add_layer("dots").set_pixel(1, 0, Some(red), None, None);
+        layers.add_layer("text").draw_text(2, 0, "!", Some(red), None);
+
+        let screen = layers.compose();
+        assert_eq!(screen.get_pixel(0, 0).0, blue);
+        assert_eq!(screen.get_pixel(1, 0).0, red);
+        assert_eq!(screen.get_pixel(1, 0).2, 'o');
+        assert_eq!(screen.get_pixel(2, 0).2, '!');
+
+        assert!(layers.remove_layer("dots").is_some());
+        assert!(layers.remove_layer("dots").is_none());
+        assert_eq!(layers.compose().get_pixel(1, 0).0, blue);
+
+        // adding a layer again starts it over on top
+        layers.layer_mut("board").unwrap().set_pixel(0, 0, None, None, Some('b'));
+        layers.add_layer("text");
+        assert_eq!(layers.layer("board").unwrap().get_pixel(0, 0).2, 'b');
+        assert_eq!(layers.compose().get_pixel(2, 0).2, 'o');
+    }
+
+    #[test]
+    fn opening_book() {
+        let mut game = chess::Game::default();
+        assert_eq!(game.book_move(), Some(chess::Move::new(4, 1, 4, 3)));
+        assert_eq!(game.best_move(4), game.book_move());
+
+        // every book move is legal and leads through the book's lines
+        for line in [&["e2e4", "e7e5", "g1f3", "b8c6", "f1b5"][..], &["d2d4", "g8f6", "c2c4"], &["c2c4", "e7e5"], &["g1f3", "d7d5"]] {
+            let mut game = chess::Game::default();
+            for uci in line {
+                let book_moves = game.book_moves();
+                let legal_moves = game.get_all_legal_moves();
+                assert!(!book_moves.is_empty());
+                assert!(book_moves.iter().all(|m| legal_moves.contains(m)));
+                assert!(game.try_make_move_uci(uci));
+            }
+        }
+
+        assert!(game.try_make_move_uci("a2a3"));
+        assert_eq!(game.book_move(), None);
+    }
+
+    #[test]
+    fn piece_values() {
+        const QUEEN: i32 = chess::PieceType::Queen.value();
+        assert_eq!(QUEEN, 900);
+        assert_eq!(chess::PieceType::Pawn.value(), 100);
+        assert_eq!(chess::PieceType::Knight.value(), 320);
+        assert_eq!(chess::PieceType::Bishop.value(), 330);
+        assert_eq!(chess::PieceType::Rook.value(), 500);
+        assert!(chess::PieceType::King.value() > 8 * 100 + 2 * (320 + 330 + 500) + 9 * 900);
+    }
+
+    #[test]
+    fn static_exchange_evaluation() {
+        let game = fen::from_piece_list(&[
+            (chess::PieceType::King, chess::Color::White, "g1"),
+            (chess::PieceType::Rook, chess::Color::White, "e2"),
+            (chess::PieceType::Rook, chess::Color::White, "e1"),
+            (chess::PieceType::Knight, chess::Color::White, "c3"),
+            (chess::PieceType::Pawn, chess::Color::White, "c4"),
+            (chess::PieceType::King, chess::Color::Black, "g8"),
+            (chess::PieceType::Rook, chess::Color::Black, "e8"),
+            (chess::PieceType::Pawn, chess::Color::Black, "e5"),
+            (chess::PieceType::Knight, chess::Color::Black, "d5"),
+            (chess::PieceType::Pawn, chess::Color::Black, "a5"),
+            (chess::PieceType::Pawn, chess::Color::Black, "b6"),
+        ]).unwrap();
+
+        // the rook behind the first one joins the exchange on e5
+        assert_eq!(game.attackers(4, 4, chess::Color::White), vec![(4, 1)]);
+        assert_eq!(game.attackers(4, 4, chess::Color::Black), vec![(4, 7)]);
+        assert_eq!(game.see(4, 1, 4, 4), 100);
+        // the knight on d5 is defended by nothing, the pawn takes it for free
+        assert_eq!(game.see(2, 3, 3, 4), 320);
+        assert_eq!(game.see(2, 2, 3, 4), 320);
+        // the knight on c3 runs into the pawn on b6
+        assert_eq!(game.see(2, 2, 0, 4), 100 - 320);
+        assert_eq!(game.see(2, 2, 1, 4), 0);
+        assert_eq!(game.see(2, 2, 1, 3), -320);
+
+        assert_eq!(game.checkers(), Vec::new());
+        assert_eq!(game.attackers(1, 3, chess::Color::Black), vec![(0, 4), (3, 4)]);
+    }
+
+    #[test]
+    fn parse_fen() {
+        let game = fen::parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        assert!(game.same_position(&chess::Game::default()));
+        assert_eq!(game.fullmove_number(), 1);
+
+        // with Black to move the board is seen from Black's side
+        let game = fen::parse("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 12").unwrap();
+        assert_eq!(game.player_to_move(), chess::Color::Black);
+        assert_eq!(game.fullmove_number(), 12);
+        assert_eq!(game.ply(), 23);
+        assert_eq!(game.get_piece(4, 0), Some(chess::Piece::new(chess::PieceType::King, chess::Color::Black)));
+        assert_eq!(game.get_piece(4, 4), Some(chess::Piece::new(chess::PieceType::Pawn, chess::Color::White)));
+
+        assert_eq!(fen::parse("8/8/8/8/8/8/8 w - -").err(), Some(fen::FenError::InvalidPlacement("8/8/8/8/8/8/8".to_string())));
+        assert_eq!(fen::parse("8/8/8/8/8/8/8/9 w - -").err(), Some(fen::FenError::InvalidPlacement("8/8/8/8/8/8/8/9".to_string())));
+        assert_eq!(fen::parse("8/8/8/8/8/8/8/8 x - -").err(), Some(fen::FenError::InvalidSideToMove("x".to_string())));
+        assert_eq!(fen::parse("8/8/8/8/8/8/8/8 w KX -").err(), Some(fen::FenError::InvalidCastling("KX".to_string())));
+        assert_eq!(fen::parse("8/8/8/8/8/8/8/8 w - e4").err(), Some(fen::FenError::InvalidEnPassant("e4".to_string())));
+        assert_eq!(fen::parse("8/8/8/8/8/8/8/8 w - - 0 0").err(), Some(fen::FenError::InvalidCounter("0".to_string())));
+        assert_eq!(fen::parse("8/8/8/8/8/8/8/8 w").err(), Some(fen::FenError::MissingField("castling")));
+    }
+
+    #[test]
+    fn from_fen() {
+        let fen = "4k3/8/8/8/4P3/8/8/4K3 b - - 0 12";
+        let game = chess::Game::from_fen(fen).unwrap();
+        assert!(game.same_position(&fen::parse(fen).unwrap()));
+        assert_eq!(game.fullmove_number(), 12);
+
+        let parsed: chess::Game = fen.parse().unwrap();
+        assert!(parsed.same_position(&game));
+        assert_eq!("not a fen".parse::<chess::Game>().err(), Some(fen::FenError::InvalidPlacement("not".to_string())));
+    }
+
+    #[test]
+    fn board_from_ascii() {
+        let board = chess::Board::from_ascii(&[
+            "r n b q k b n r",
+            "p p p p p p p p",
+            ". . . . . . . .",
+            ". . . . . . . .",
+            ". . . . . . . .",
+            ". . . . . . . .",
+            "P P P P P P P P",
+            "R N B Q K B N R",
+        ]).unwrap();
+        assert!(board == chess::Board::default());
+
+        let board = chess::Board::from_ascii(&["....k...", "........", "........", "........", "....P...", "........", "........", "....K..."]).unwrap();
+        assert!(chess::Game::new(board).same_position(&fen::parse("4k3/8/8/8/4P3/8/8/4K3 w - -").unwrap()));
+
+        let rows = ["....k...", "........", "........", "........", "....X...", "........", "........", "....K..."];
+        assert!(chess::Board::from_ascii(&rows).err() == Some(fen::FenError::InvalidPiece('X')));
+        let rows = ["....k...", "........", "........", "........", ".......", "........", "........", "....K..."];
+        assert!(chess::Board::from_ascii(&rows).err() == Some(fen::FenError::InvalidPlacement(".......".to_string())));
+
+        assert_eq!(chess::Piece::from_fen_char('n'), Some(chess::Piece::new(chess::PieceType::Knight, chess::Color::Black)));
+        assert_eq!(chess::Piece::new(chess::PieceType::Queen, chess::Color::White).to_fen_char(), 'Q');
+        assert_eq!(chess::Piece::from_fen_char('x'), None);
+    }
+
+    #[test]
+    fn starting_fen() {
+        let game = fen::parse(fen::STARTING_FEN).unwrap();
+        assert!(game == chess::Game::default());
+        assert_eq!(fen::to_fen(&chess::Game::default()), fen::STARTING_FEN);
+
+        let mut game = chess::Game::default();
+        assert!(game.try_make_move_uci("e2e4"));
+        assert_eq!(game.to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+        assert!(game.try_make_move_uci("g8f6"));
+        assert!(game.try_make_move_uci("e1e2"));
+        assert!(game.try_make_move_uci("h8g8"));
+        assert_eq!(game.to_fen(), "rnbqkbr1/pppppppp/5n2/8/4P3/8/PPPPKPPP/RNBQ1BNR w q - 3 3");
+        assert!(game.undo_move());
+        assert!(game.undo_move());
+        assert_eq!(game.castling_rights(), chess::CastlingRights::ALL);
+        assert_eq!(game.halfmove_clock(), 1);
+
+        for fen in ["4k2r/8/8/8/8/8/8/R3K3 b Qk - 12 40", "8/8/8/2k5/8/8/8/4K3 w - - 0 1"] {
+            assert_eq!(fen::parse(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn mirrored() {
+        let game = fen::parse("r3k2r/pp3ppp/2n5/3qp3/8/2N2N2/PPP2PPP/R3K2R w KQk - 4 9").unwrap();
+        let mirrored = game.mirrored();
+        assert_eq!(mirrored.to_fen(), "r3k2r/ppp2ppp/2n2n2/8/3QP3/2N5/PP3PPP/R3K2R b Kkq - 4 9");
+        assert_eq!(mirrored.evaluate(), -game.evaluate());
+        assert_eq!(mirrored.evaluate_positional(), -game.evaluate_positional());
+        assert_eq!(mirrored.evaluate_mobility(), -game.evaluate_mobility());
+        assert!(mirrored.mirrored().same_position(&game));
+        assert_eq!(mirrored.mirrored().castling_rights(), game.castling_rights());
+
+        let mut start = chess::Game::default();
+        assert!(start.try_make_move_uci("e2e4"));
+        assert_eq!(start.mirrored().to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    }
+
+    #[test]
+    fn parse_epd() {
+        let line = "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - bm Ra8#; id \"back rank; mate\"; c0 \"\";";
+        let (mut game, operations) = epd::parse(line).unwrap();
+        assert_eq!(operations.len(), 3);
+        assert_eq!(operations["id"], "back rank; mate");
+        assert_eq!(operations["c0"], "");
+
+        let best = notation::parse_san(&mut game, &operations["bm"]);
+        assert!(best.is_some());
+        assert_eq!(game.best_move(2), best);
+
+        assert!(epd::parse("6k1/8/8/8/8/8/8/6K1 w - -").unwrap().1.is_empty());
+        assert_eq!(epd::parse("6k1/8/8/8/8/8/8/6K1 w - - bm Kg2").err(), Some(epd::EpdError::InvalidOperation("bm Kg2".to_string())));
+        assert_eq!(epd::parse("6k1/8/8/8/8/8/8/6K1 w - - id \"x;").err(), Some(epd::EpdError::UnterminatedString("id \"x;".to_string())));
+        assert_eq!(epd::parse("6k1/8/8/8/8/8/8/6K1 w").err(), Some(epd::EpdError::Fen(fen::FenError::MissingField("castling"))));
+    }
+
+    #[test]
+    fn draw_border() {
+        let mut screen = graphics::Screen::new(5, 4);
+        screen.draw_border(0, 0, 4, 3, graphics::Color::new(200, 0, 0));
+        let rows: Vec<String> = (0..4).map(|y| (0..5).map(|x| screen.get_pixel(x, y).2).collect()).collect();
+        assert_eq!(rows, vec!["┌──┐ ", "│  │ ", "└──┘ ", "     "]);
+        assert_eq!(screen.get_pixel(0, 0).1, graphics::Color::new(200, 0, 0));
+
+        // clipped at the right and bottom edges
+        screen.draw_border(3, 2, 10, 10, graphics::Color::new(0, 0, 0));
+        assert_eq!(screen.get_pixel(3, 2).2, '┌');
+        assert_eq!(screen.get_pixel(4, 2).2, '─');
+        assert_eq!(screen.get_pixel(3, 3).2, '│');
+        assert_eq!(screen.get_pixel(4, 3).2, ' ');
+
+        // starting off the screen, or on an empty one, draws nothing
+        screen.draw_border(5, 0, 2, 2, graphics::Color::new(0, 0, 0));
+        screen.draw_border(0, 4, 2, 2, graphics::Color::new(0, 0, 0));
+        assert_eq!(screen.get_pixel(4, 0).2, ' ');
+        assert_eq!(screen.get_pixel(0, 3).2, ' ');
+        let mut empty = graphics::Screen::new(0, 0);
+        empty.draw_border(0, 0, 3, 3, graphics::Color::new(0, 0, 0));
+        empty.draw_border(0, 0, usize::MAX, usize::MAX, graphics::Color::new(0, 0, 0));
+        assert_eq!(empty, graphics::Screen::new(0, 0));
+    }
+
+    #[test]
+    fn draw_circle() {
+        let red = graphics::Color::new(200, 0, 0);
+        let mask = |screen: &graphics::Screen| -> Vec<String> {
+            (0..screen.height()).map(|y| (0..screen.width()).map(|x| if screen.get_pixel(x, y).0 == red { '#' } else { '.' }).collect()).collect()
+        };
+
+        let mut screen = graphics::Screen::new(7, 7);
+        screen.draw_circle(3, 3, 3, red);
+        assert_eq!(mask(&screen), vec![
+            "..###..",
+            ".#...#.",
+            "#.....#",
+            "#.....#",
+            "#.....#",
+            ".#...#.",
+            "..###.."
+        ]);
+
+        let mut screen = graphics::Screen::new(7, 7);
+        screen.fill_circle(3, 3, 2, red);
+        assert_eq!(mask(&screen), vec![
+            ".......",
+            "..###..",
+            ".#####.",
+            ".#####.",
+            ".#####.",
+            "..###..",
+            "......."
+        ]);
+
+        // half as tall, and clipped at the top left
+        let mut screen = graphics::Screen::new(7, 3);
+        screen.draw_ellipse(0, 0, 4, 0.5, red, false);
+        assert_eq!(mask(&screen), vec![
+            "....#..",
+            "...#...",
+            "###...."
+        ]);
+
+        // squashed flat, it's a line across the full width, clipped at the left
+        let mut screen = graphics::Screen::new(7, 3);
+        screen.draw_ellipse(1, 1, 2, 0.0, red, false);
+        assert_eq!(mask(&screen), vec![
+            ".......",
+            "####...",
+            "......."
+        ]);
+        let mut screen = graphics::Screen::new(7, 3);
+        screen.draw_ellipse(3, 1, 1, 0.4, red, false);
+        assert_eq!(mask(&screen), vec![
+            ".......",
+            "..###..",
+            "......."
+        ]);
+    }
+
+    #[test]
+    fn legal_move_count() {
+        let mut game = chess::Game::default();
+        assert_eq!(game.legal_move_count(), 20);
+        assert!(game.has_legal_move());
+        assert!(game.try_make_move_uci("e2e4"));
+        assert_eq!(game.legal_move_count(), game.get_all_legal_moves().len());
+
+        // en passant, promotions, a pinned piece and captures all counted the same way
+        for uci in ["a7a6", "e4e5", "d7d5"] {
+            assert!(game.try_make_move_uci(uci));
+        }
+        assert_eq!(game.legal_move_count(), game.get_all_legal_moves().len());
+        let mut game = fen::parse("r3k3/1P6/8/8/4r3/8/4N3/4K3 w - - 0 1").unwrap();
+        assert_eq!(game.legal_move_count(), game.get_all_legal_moves().len());
+
+        let mut mate = fen::parse("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - -").unwrap();
+        assert!(!mate.is_checkmate());
+        assert!(mate.try_make_move_uci("a1a8"));
+        assert_eq!(mate.legal_move_count(), 0);
+        assert!(!mate.has_legal_move());
+        assert!(mate.is_checkmate());
+        assert!(!mate.is_stalemate());
+
+        let mut stalemate = fen::parse("7k/5Q2/6K1/8/8/8/8/8 b - -").unwrap();
+        assert!(!stalemate.has_legal_move());
+        assert!(stalemate.is_stalemate());
+        assert!(!stalemate.is_checkmate());
+    }
+
+    #[test]
+    fn draw_agreement_and_resignation() {
+        let mut game = chess::Game::default();
+        assert_eq!(game.result(), None);
+        assert!(!game.accept_draw(chess::Color::Black));
+
+        // a move by the other side turns the offer down
+        game.offer_draw(chess::Color::White);
+        assert!(!game.accept_draw(chess::Color::White));
+        assert!(game.try_make_move_uci("e2e4"));
+        assert!(game.try_make_move_uci("e7e5"));
+        assert!(!game.accept_draw(chess::Color::Black));
+
+        game.offer_draw(chess::Color::White);
+        assert!(game.try_make_move_uci("g1f3"));
+        assert!(game.accept_draw(chess::Color::Black));
+        assert_eq!(game.result(), Some(chess::GameResult::Draw(chess::DrawReason::Agreement)));
+        game.resign(chess::Color::White);
+        assert_eq!(game.result(), Some(chess::GameResult::Draw(chess::DrawReason::Agreement)));
+
+        let mut game = chess::Game::default();
+        game.resign(chess::Color::White);
+        assert_eq!(game.result(), Some(chess::GameResult::BlackWins));
+
+        let mut mate = fen::parse("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - -").unwrap();
+        assert!(mate.try_make_move_uci("a1a8"));
+        assert_eq!(mate.result(), Some(chess::GameResult::WhiteWins));
+        let mut stalemate = fen::parse("7k/5Q2/6K1/8/8/8/8/8 b - -").unwrap();
+        assert_eq!(stalemate.result(), Some(chess::GameResult::Draw(chess::DrawReason::Stalemate)));
+    }
+
+    #[test]
+    fn automatic_draws() {
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+        let mut game = chess::Game::default();
+        for _ in 0..2 {
+            for m in shuffle {
+                assert!(game.try_make_move_uci(m));
+            }
+        }
+        assert_eq!(game.repetition_count(), 3);
+        assert!(game.is_threefold_repetition());
+        assert_eq!(game.history().len(), 8);
+        // left to the players unless asked for
+        assert_eq!(game.result(), None);
+
+        let mut game = chess::Game::default();
+        game.set_auto_draw(true);
+        for m in shuffle.iter().chain(&shuffle[..3]) {
+            assert!(game.try_make_move_uci(m));
+        }
+        assert_eq!(game.result(), None);
+        assert!(game.try_make_move_uci("f6g8"));
+        assert_eq!(game.result(), Some(chess::GameResult::Draw(chess::DrawReason::ThreefoldRepetition)));
+        assert!(game.undo_move());
+        assert_eq!(game.result(), None);
+        assert!(game.redo_move());
+        assert_eq!(game.result(), Some(chess::GameResult::Draw(chess::DrawReason::ThreefoldRepetition)));
+
+        let mut game = fen::parse("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+        game.set_auto_draw(true);
+        assert!(!game.is_fifty_move_draw());
+        assert!(game.try_make_move_uci("a1a2"));
+        assert!(game.is_fifty_move_draw());
+        assert_eq!(game.result(), Some(chess::GameResult::Draw(chess::DrawReason::FiftyMoveRule)));
+
+        // mate on the hundredth halfmove still counts as mate
+        let mut game = fen::parse("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 99 80").unwrap();
+        game.set_auto_draw(true);
+        assert!(game.try_make_move_uci("a1a8"));
+        assert_eq!(game.result(), Some(chess::GameResult::WhiteWins));
+    }
+
+    #[test]
+    fn underpromotion() {
+        // only promoting to a knight mates, since a queen on e8 isn't check
+        let mut game = fen::parse("5nbr/4Ppkp/6pp/8/8/8/8/4K3 w - -").unwrap();
+        let fen = game.to_fen();
+        let knight = chess::Move::new_promotion(4, 6, 4, 7, chess::PieceType::Knight);
+
+        let moves = game.get_all_legal_moves();
+        let pushes: Vec<chess::Move> = moves.iter().copied().filter(|m| m.from() == (4, 6) && m.to() == (4, 7)).collect();
+        assert_eq!(pushes.iter().map(|m| m.promotion()).collect::<Vec<_>>(), vec![
+            Some(chess::PieceType::Queen),
+            Some(chess::PieceType::Rook),
+            Some(chess::PieceType::Bishop),
+            Some(chess::PieceType::Knight)
+        ]);
+        assert_eq!(moves.iter().filter(|m| m.from() == (4, 6)).count(), 8);
+        assert_eq!(game.legal_move_count(), moves.len());
+        assert_eq!(game.best_move(2), Some(knight));
+
+        assert_eq!(notation::to_san(&mut game, knight), Some("e8=N#".to_string()));
+        assert_eq!(notation::to_san(&mut game, chess::Move::new(4, 6, 4, 7)), Some("e8=Q".to_string()));
+        assert_eq!(notation::to_san(&mut game, chess::Move::new_promotion(4, 6, 5, 7, chess::PieceType::Rook)), Some("exf8=R".to_string()));
+        assert_eq!(notation::parse_san(&mut game, "e8=N"), Some(knight));
+        assert_eq!(notation::parse_san(&mut game, "e8N#"), Some(knight));
+        assert_eq!(notation::parse_san(&mut game, "e8").and_then(|m| m.promotion()), Some(chess::PieceType::Queen));
+        assert_eq!(notation::parse_uci(&game, "e7e8n"), Some(knight));
+        assert_eq!(notation::parse_uci(&game, "e7e8").and_then(|m| m.promotion()), Some(chess::PieceType::Queen));
+        assert_eq!(notation::parse_uci(&game, "e7e8k"), None);
+
+        assert_eq!(game.try_play_move(chess::Move::new_promotion(4, 6, 4, 7, chess::PieceType::King)), Err(chess::MoveError::NotPseudoLegal));
+        assert_eq!(game.try_play_move(chess::Move::new_promotion(4, 0, 4, 1, chess::PieceType::Knight)), Err(chess::MoveError::NotPseudoLegal));
+        assert!(game.try_make_move_uci("e7e8n"));
+        assert_eq!(game.history().last().and_then(|record| record.promotion()), Some(chess::PieceType::Knight));
+        assert_eq!(game.result(), Some(chess::GameResult::WhiteWins));
+        assert!(game.undo_move());
+        assert_eq!(game.to_fen(), fen);
+        assert!(game.redo_move());
+        assert_eq!(game.to_fen(), "4Nnbr/5pkp/6pp/8/8/8/8/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn apply_moves() {
+        let mut game = chess::Game::default();
+        assert_eq!(game.apply_moves(&["e4", "e7e5", "Nf3", "Nc6"]), Ok(()));
+        assert_eq!(game.to_fen(), "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+
+        // the moves before the bad one stay played
+        let mut game = chess::Game::default();
+        assert_eq!(game.apply_moves(&["d4", "d5", "Ke3", "Nf3"]), Err(2));
+        assert_eq!(game.history().len(), 2);
+        assert_eq!(game.apply_moves(&["c4", "zz"]), Err(1));
+        assert_eq!(game.apply_moves(&[]), Ok(()));
+    }
+
+    #[test]
+    fn attack_ray() {
+        let game = fen::parse("4k3/8/8/8/R2p4/8/8/4K3 w - -").unwrap();
+        let ray = game.attack_ray(0, 3, 1, 0);
+        assert_eq!(ray.squares(), &[(1, 3), (2, 3)]);
+        assert_eq!(ray.blocker(), Some(((3, 3), chess::Piece::new(chess::PieceType::Pawn, chess::Color::Black))));
+
+        let ray = game.attack_ray(0, 3, 0, -1);
+        assert_eq!(ray.squares(), &[(0, 2), (0, 1), (0, 0)]);
+        assert_eq!(ray.blocker(), None);
+
+        assert!(game.attack_ray(0, 3, -1, 0).squares().is_empty());
+        assert_eq!(game.attack_ray(0, 3, 0, 0).blocker(), None);
+    }
+
+    #[test]
+    fn legal_move_list() {
+        let mut game = fen::parse("3rk3/4P3/8/8/8/8/8/4K3 w - -").unwrap();
+        let moves = game.get_legal_move_list(4, 6);
+        assert_eq!(moves.len(), 4);
+        assert!(moves.iter().all(|m| m.is_promotion() && m.to() == (3, 7)));
+
+        let moves = game.get_legal_move_list(4, 0);
+        assert_eq!(moves.iter().map(|m| m.to()).collect::<Vec<_>>(), vec![(5, 0), (4, 1), (5, 1)]);
+        assert!(moves.iter().all(|m| !m.is_promotion()));
+        assert!(game.get_legal_move_list(3, 7).is_empty());
+    }
+
+    #[test]
+    fn legal_moves_for() {
+        let mut game = chess::Game::default();
+        assert!(game.try_make_move_uci("e2e4"));
+        let fen = game.to_fen();
+
+        // Black is to move, so White's rank 2 is row 6
+        let white = game.legal_moves_for(chess::Color::White);
+        assert_eq!(white.len(), 30);
+        assert!(white.contains(&chess::Move::new(3, 6, 3, 4)));
+        assert!(white.contains(&chess::Move::new(4, 4, 4, 3)));
+        assert!(!white.contains(&chess::Move::new(4, 4, 4, 5)));
+        assert_eq!(game.legal_moves_for(chess::Color::Black), game.get_all_legal_moves());
+        assert_eq!(game.to_fen(), fen);
+        let key = |m: &chess::Move| (m.from().1, m.from().0, m.to().1, m.to().0);
+        assert!(white.windows(2).all(|pair| key(&pair[0]) <= key(&pair[1])));
+
+        // White's king may not step onto the rank the rook covers
+        let game = fen::parse("4k3/8/8/8/8/8/r7/4K3 b - -").unwrap();
+        let white = game.legal_moves_for(chess::Color::White);
+        assert!(white.iter().all(|m| m.to().1 != 6));
+
+        // a White pawn about to promote with Black to move, so White's rank 7 is row 1
+        let game = fen::parse("4k3/1P6/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        let promotions: Vec<Option<chess::PieceType>> = game.legal_moves_for(chess::Color::White).into_iter()
+            .filter(|m| m.from() == (1, 1))
+            .inspect(|m| assert_eq!(m.to(), (1, 0)))
+            .map(|m| m.promotion())
+            .collect();
+        assert_eq!(promotions, vec![
+            Some(chess::PieceType::Queen),
+            Some(chess::PieceType::Rook),
+            Some(chess::PieceType::Bishop),
+            Some(chess::PieceType::Knight),
+        ]);
+    }
+
+    #[test]
+    fn double_push_from_home_rank() {
+        let mut game = fen::parse("4k3/p7/1p6/8/8/2P5/1P1P4/4K3 w - -").unwrap();
+        assert_eq!(game.get_legal_moves(2, 2), HashSet::from([(2, 3)]));
+        assert_eq!(game.get_legal_moves(1, 1), HashSet::from([(1, 2), (1, 3)]));
+        assert!(game.is_double_push(1, 1, 1, 3));
+        assert!(!game.is_double_push(2, 2, 2, 4));
+
+        assert!(game.try_make_move_uci("d2d4"));
+        // Black's pawns as seen from Black's side
+        assert_eq!(game.get_legal_moves(0, 1), HashSet::from([(0, 2), (0, 3)]));
+        assert_eq!(game.get_legal_moves(1, 2), HashSet::from([(1, 3)]));
+        assert!(game.is_double_push(0, 1, 0, 3));
+        assert!(game.is_double_push(1, 6, 1, 4));
+    }
+
+    #[test]
+    fn clock() {
+        use std::time::Duration;
+
+        let mut clock = clock::Clock::new(Duration::from_secs(60), Duration::from_secs(2));
+        assert_eq!(clock.running(), None);
+        assert_eq!(clock.remaining(chess::Color::White), Duration::from_secs(60));
+
+        clock.start(chess::Color::White);
+        assert_eq!(clock.running(), Some(chess::Color::White));
+        clock.press();
+        assert_eq!(clock.running(), Some(chess::Color::Black));
+        assert!(clock.remaining(chess::Color::White) > Duration::from_secs(61));
+        assert!(clock.remaining(chess::Color::Black) <= Duration::from_secs(60));
+        clock.stop();
+        assert_eq!(clock.running(), None);
+        assert_eq!(clock.flagged(), None);
+
+        let mut clock = clock::Clock::new(Duration::from_millis(5), Duration::from_secs(2));
+        clock.start(chess::Color::Black);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(clock.flagged(), Some(chess::Color::Black));
+        // no increment once the flag has fallen
+        clock.press();
+        assert_eq!(clock.remaining(chess::Color::Black), Duration::ZERO);
+        assert_eq!(clock.flagged(), Some(chess::Color::Black));
+    }
+
+    #[test]
+    fn is_square_attacked_by() {
+        let game = fen::parse("4k3/8/8/8/8/5n2/8/R3K2R w KQ - 0 1").unwrap();
+        // the knight on f3 covers g1 and d2, keeping the king from castling short
+        assert!(game.is_square_attacked_by(6, 0, chess::Color::Black));
+        assert!(game.is_square_attacked_by(3, 1, chess::Color::Black));
+        assert!(!game.is_square_attacked_by(2, 0, chess::Color::Black));
+        assert!(game.is_square_attacked_by(5, 0, chess::Color::White));
+        assert!(!game.is_square_attacked_by(4, 4, chess::Color::White));
+        for y in 0..8 {
+            for x in 0..8 {
+                for color in chess::ALL_COLORS {
+                    assert_eq!(game.is_square_attacked_by(x, y, color), !game.attackers(x, y, color).is_empty());
                 }
             }
         }
     }
 
-    fn draw_moves(board_x: usize, board_y: usize, moves: &HashSet<(usize, usize)>, color: graphics::Color, screen: &mut Screen) -> () {
-        for (x, y) in moves {
-            screen.set_pixel(board_x + x, board_y + y, Some(color), None, None)
+    #[test]
+    fn move_errors() {
+        let mut game = fen::parse("4k3/8/8/8/8/8/4R3/4K3 b - - 0 1").unwrap();
+        // rows are counted from Black's side
+        assert_eq!(game.try_make_move_checked(0, 0, 0, 1), Err(chess::MoveError::EmptySquare));
+        assert_eq!(game.try_make_move_checked(4, 6, 4, 5), Err(chess::MoveError::WrongColor));
+        assert_eq!(game.try_make_move_checked(4, 0, 4, 2), Err(chess::MoveError::NotPseudoLegal));
+        assert_eq!(game.try_make_move_checked(4, 0, 4, 1), Err(chess::MoveError::LeavesKingInCheck));
+        assert!(!game.try_make_move(4, 0, 4, 1));
+        assert!(game.history().is_empty());
+
+        assert_eq!(game.try_make_move_checked(4, 0, 3, 0), Ok(()));
+        assert_eq!(game.history().len(), 1);
+    }
+
+    #[test]
+    fn san_check_suffixes() {
+        let mut game = chess::Game::default();
+        for san in ["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7#"] {
+            let m = notation::parse_san(&mut game, san).unwrap();
+            assert_eq!(notation::to_san(&mut game, m).as_deref(), Some(san));
+            assert!(game.try_make_move_san(san));
+        }
+        assert!(game.is_checkmate());
+
+        let mut game = chess::Game::default();
+        assert!(game.try_make_move_san("e4"));
+        assert!(game.try_make_move_san("f5"));
+        let m = notation::parse_san(&mut game, "Qh5").unwrap();
+        assert_eq!(notation::to_san(&mut game, m).as_deref(), Some("Qh5+"));
+        assert!(game.try_make_move_san("exf5"));
+        assert!(game.try_make_move_san("g6"));
+        let m = notation::parse_san(&mut game, "fxg6").unwrap();
+        assert_eq!(notation::to_san(&mut game, m).as_deref(), Some("fxg6"));
+        assert_eq!(notation::to_san(&mut game, chess::Move::new(0, 0, 0, 5)), None);
+        assert_eq!(game.history().len(), 4);
+    }
+
+    #[test]
+    fn san_disambiguation() {
+        let san = |game: &mut chess::Game, uci: &str| -> String {
+            let m = notation::parse_uci(game, uci).unwrap();
+            notation::to_san(game, m).unwrap()
+        };
+
+        // knights on b1 and f3 both reach d2, rooks doubled on the a-file both reach a4
+        let mut game = fen::parse("6k1/8/8/R7/8/8/8/RN3NK1 w - - 0 1").unwrap();
+        assert_eq!(san(&mut game, "b1d2"), "Nbd2");
+        assert_eq!(san(&mut game, "f1d2"), "Nfd2");
+        assert_eq!(san(&mut game, "b1c3"), "Nc3");
+        assert_eq!(san(&mut game, "a1a3"), "R1a3");
+        assert_eq!(san(&mut game, "a5a3"), "R5a3");
+        assert_eq!(san(&mut game, "a5b5"), "Rb5");
+
+        // three queens where neither file nor rank alone is enough for the one on d4
+        let mut game = fen::parse("k7/8/8/8/3Q3Q/8/3Q4/K7 w - - 0 1").unwrap();
+        assert_eq!(san(&mut game, "d4f2"), "Qd4f2");
+        assert_eq!(san(&mut game, "h4f2"), "Qhf2");
+        assert_eq!(san(&mut game, "d2f2"), "Q2f2");
+
+        for uci in ["d4f2", "h4f2", "d2f2", "d2d3"] {
+            let m = notation::parse_uci(&game, uci).unwrap();
+            let text = notation::to_san(&mut game, m).unwrap();
+            assert_eq!(notation::parse_san(&mut game, &text), Some(m));
         }
     }
 
     #[test]
-    fn test() -> () {
+    fn to_unicode_string() {
+        let mut game = chess::Game::default();
+        assert!(game.try_make_move_uci("e2e4"));
+        assert_eq!(game.to_unicode_string(chess::Color::White), "\
+8 ♜ ♞ ♝ ♛ ♚ ♝ ♞ ♜
+7 ♟ ♟ ♟ ♟ ♟ ♟ ♟ ♟
+6 . . . . . . . .
+5 . . . . . . . .
+4 . . . . ♙ . . .
+3 . . . . . . . .
+2 ♙ ♙ ♙ ♙ . ♙ ♙ ♙
+1 ♖ ♘ ♗ ♕ ♔ ♗ ♘ ♖
+  a b c d e f g h
+");
+        assert_eq!(game.to_unicode_string(chess::Color::Black), "\
+1 ♖ ♘ ♗ ♔ ♕ ♗ ♘ ♖
+2 ♙ ♙ ♙ . ♙ ♙ ♙ ♙
+3 . . . . . . . .
+4 . . . ♙ . . . .
+5 . . . . . . . .
+6 . . . . . . . .
+7 ♟ ♟ ♟ ♟ ♟ ♟ ♟ ♟
+8 ♜ ♞ ♝ ♚ ♛ ♝ ♞ ♜
+  h g f e d c b a
+");
+    }
+
+    #[test]
+    fn test() {
         
-        let mut game = chess::Game::new(chess::Board::new(chess::BOARD_DEFAULT_SETUP));
+        let mut game = chess::Game::default();
+        let theme = render::BoardTheme::default();
 
         let mut screen: graphics::Screen = graphics::Screen::new(10, 10);
 
@@ -82,7 +2110,7 @@ mod tests {
         loop {
             screen.clear(Some(graphics::Color::new(48, 48, 64)), None, Some(' '));
 
-            draw_board(&game, board_x, board_y, &mut screen);
+            render::draw_board(&game, board_x, board_y, &mut screen, &theme);
 
             for i in 0..8 {
                 screen.set_pixel(board_x + i, board_y - 1, None, Some(graphics::Color::new(128, 128, 196)), Some(FILE_CHARS[i]));
@@ -92,9 +2120,9 @@ mod tests {
             }
             
             if let Some((from_x, from_y)) = from {
-                screen.set_pixel(board_x + from_x, board_y + from_y, Some(graphics::Color::new(255, 255, 196)), None, None);
+                screen.set_pixel(board_x + from_x, board_y + from_y, Some(theme.highlight), None, None);
                 let moves = game.get_legal_moves(from_x, from_y);
-                draw_moves(board_x, board_y, &moves, graphics::Color::new(128, 128, 196), &mut screen);
+                render::draw_moves(&moves, board_x, board_y, &mut screen, &theme);
             }
             
             screen.set_pixel(board_x + cursor_x, board_y + cursor_y, Some(graphics::Color::new(232, 232, 196)), None, None);
@@ -104,11 +2132,7 @@ mod tests {
                 chess::Color::Black => screen.set_pixel(0, 0, Some(graphics::Color::new(16, 16, 16)), Some(graphics::Color::new(196, 196, 196)), Some('B'))
             };
 
-            let render = screen.render(false, true);
-
-            println!("\x1B[H");
-            println!("\x1B[2J");
-            println!("{}", render);
+            screen.present(false, true).expect("couldn't draw to the terminal");
 
             if game.is_checkmate() {
                 println!("Checkmate!");
@@ -121,30 +2145,28 @@ mod tests {
             }
             
             match io::stdin().lines().next() {
-                Some(input) => match input {
-                    Ok(line) => for c in line.chars() {
-                        match c.to_ascii_uppercase() {
-                            'W' => if cursor_y < 8 - 1 { cursor_y += 1; },
-                            'A' => if cursor_x >= 1 { cursor_x -= 1; },
-                            'S' => if cursor_y >= 1 { cursor_y -= 1; },
-                            'D' => if cursor_x < 8 - 1 { cursor_x += 1; },
-                            ' ' => {
-                                if from.is_none() {
-                                    from = Some((cursor_x, cursor_y));
-                                } else {
-                                    to = Some((cursor_x, cursor_y));
-                                }
-                            },
-                            '.' => {
-                                from = None;
-                                to = None;
+                Some(Ok(line)) => for c in line.chars() {
+                    match c.to_ascii_uppercase() {
+                        'W' if cursor_y < 8 - 1 => cursor_y += 1,
+                        'A' if cursor_x >= 1 => cursor_x -= 1,
+                        'S' if cursor_y >= 1 => cursor_y -= 1,
+                        'D' if cursor_x < 8 - 1 => cursor_x += 1,
+                        ' ' => {
+                            if from.is_none() {
+                                from = Some((cursor_x, cursor_y));
+                            } else {
+                                to = Some((cursor_x, cursor_y));
                             }
-                            _ => ()
+                        },
+                        '.' => {
+                            from = None;
+                            to = None;
                         }
-                    },
-                    Err(_) => ()
-                }
-                None => ()
+                        _ => ()
+                    }
+                },
+                Some(Err(_)) => (),
+                None => return
             }
 
             if let Some((from_x, from_y)) = from {
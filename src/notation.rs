@@ -0,0 +1,181 @@
+use crate::chess::{Game, Move, PieceType, BOARD_SIZE};
+use crate::fen::parse_square;
+
+/// Parses a move in UCI notation, e.g. "e2e4" or "e7e8n", into board coordinates for the side to move.
+/// A pawn move to the last rank without a promotion letter promotes to a queen. The move is not
+/// checked for legality.
+pub fn parse_uci(game: &Game, uci: &str) -> Option<Move> {
+    if !(4..=5).contains(&uci.len()) || !uci.is_ascii() {
+        return None;
+    }
+    let (from_x, from_rank) = parse_square(&uci[0..2])?;
+    let (to_x, to_rank) = parse_square(&uci[2..4])?;
+    let (from_y, to_y) = (game.oriented(from_rank), game.oriented(to_rank));
+    match uci.chars().nth(4) {
+        Some(c) => Some(Move::new_promotion(from_x, from_y, to_x, to_y, promotion_piece(c.to_ascii_uppercase())?)),
+        None => Some(default_promotion(game, Move::new(from_x, from_y, to_x, to_y)))
+    }
+}
+
+fn promotion_piece(c: char) -> Option<PieceType> {
+    match c {
+        'Q' => Some(PieceType::Queen),
+        'R' => Some(PieceType::Rook),
+        'B' => Some(PieceType::Bishop),
+        'N' => Some(PieceType::Knight),
+        _ => None
+    }
+}
+
+// a pawn move to the last rank with no piece named promotes to a queen, which is how
+// get_all_legal_moves lists it
+fn default_promotion(game: &Game, m: Move) -> Move {
+    let (from_x, from_y) = m.from();
+    let (to_x, to_y) = m.to();
+    let is_promotion = to_y == BOARD_SIZE - 1 && game.get_piece(from_x, from_y).is_some_and(|piece| piece.piece_type() == PieceType::Pawn);
+    if is_promotion && m.promotion().is_none() {
+        Move::new_promotion(from_x, from_y, to_x, to_y, PieceType::Queen)
+    } else {
+        m
+    }
+}
+
+/// Parses a move in standard algebraic notation, e.g. "Nf3", "exd5" or "e8=N", for the side to move.
+/// A promotion without a piece given is to a queen. Returns None unless exactly one legal move matches.
+pub fn parse_san(game: &mut Game, san: &str) -> Option<Move> {
+    let san = san.trim_end_matches(['+', '#', '!', '?']);
+    let mut chars: Vec<char> = san.chars().collect();
+
+    let piece_type = match chars.first()? {
+        'K' => PieceType::King,
+        'Q' => PieceType::Queen,
+        'R' => PieceType::Rook,
+        'B' => PieceType::Bishop,
+        'N' => PieceType::Knight,
+        _ => PieceType::Pawn
+    };
+    if piece_type != PieceType::Pawn {
+        chars.remove(0);
+    }
+    let mut promotion = None;
+    if let Some(piece_type) = chars.last().and_then(|&c| promotion_piece(c)) {
+        chars.pop();
+        if chars.last() == Some(&'=') {
+            chars.pop();
+        }
+        promotion = Some(piece_type);
+    }
+    if chars.len() < 2 {
+        return None;
+    }
+
+    let destination: String = chars.split_off(chars.len() - 2).into_iter().collect();
+    let (to_x, to_rank) = parse_square(&destination)?;
+    let to_y = game.oriented(to_rank);
+
+    if chars.last() == Some(&'x') {
+        chars.pop();
+    }
+    let mut file: Option<usize> = None;
+    let mut rank: Option<usize> = None;
+    for c in chars {
+        match c {
+            'a'..='h' if file.is_none() => file = Some(c as usize - 'a' as usize),
+            '1'..='8' if rank.is_none() => rank = Some(c as usize - '1' as usize),
+            _ => return None
+        }
+    }
+
+    let mut candidates = game.get_all_legal_moves().into_iter().filter(|m| {
+        let (from_x, from_y) = m.from();
+        m.to() == (to_x, to_y)
+            && game.get_piece(from_x, from_y).is_some_and(|piece| piece.piece_type() == piece_type)
+            && file.is_none_or(|file| file == from_x)
+            && rank.is_none_or(|rank| rank == game.oriented(from_y))
+            && match promotion {
+                Some(_) => m.promotion() == promotion,
+                None => m.promotion().is_none_or(|piece_type| piece_type == PieceType::Queen)
+            }
+    });
+    let m = candidates.next()?;
+    if candidates.next().is_some() {
+        return None;
+    }
+    Some(m)
+}
+
+fn piece_letter(piece_type: PieceType) -> Option<char> {
+    match piece_type {
+        PieceType::King => Some('K'),
+        PieceType::Queen => Some('Q'),
+        PieceType::Rook => Some('R'),
+        PieceType::Bishop => Some('B'),
+        PieceType::Knight => Some('N'),
+        PieceType::Pawn => None
+    }
+}
+
+fn file_char(x: usize) -> char {
+    (b'a' + x as u8) as char
+}
+
+/// Writes a legal move of the side to move in standard algebraic notation, e.g. "Nf3", "exd5",
+/// "Nbd2" or "Qxf7#", with '+' after a check and '#' after a checkmate. Returns None if the move
+/// isn't legal.
+pub fn to_san(game: &mut Game, m: Move) -> Option<String> {
+    let m = default_promotion(game, m);
+    let legal_moves = game.get_all_legal_moves();
+    if !legal_moves.contains(&m) {
+        return None;
+    }
+    let (from_x, from_y) = m.from();
+    let (to_x, to_y) = m.to();
+    let piece = game.get_piece(from_x, from_y)?;
+    // a pawn changing file is always a capture, even onto the empty en passant square
+    let is_capture = game.get_piece(to_x, to_y).is_some() || (piece.piece_type() == PieceType::Pawn && from_x != to_x);
+
+    let mut san = String::new();
+    match piece_letter(piece.piece_type()) {
+        Some(letter) => {
+            san.push(letter);
+            // other pieces of the same type that could also go there, told apart by file, then rank, then both
+            let rivals: Vec<(usize, usize)> = legal_moves.iter()
+                .filter(|other| other.to() == m.to() && other.from() != m.from())
+                .map(|other| other.from())
+                .filter(|&(x, y)| game.get_piece(x, y).is_some_and(|other| other.piece_type() == piece.piece_type()))
+                .collect();
+            if !rivals.is_empty() {
+                let shares_file = rivals.iter().any(|&(x, _)| x == from_x);
+                let shares_rank = rivals.iter().any(|&(_, y)| y == from_y);
+                if !shares_file {
+                    san.push(file_char(from_x));
+                } else if !shares_rank {
+                    san.push_str(&(game.oriented(from_y) + 1).to_string());
+                } else {
+                    san.push(file_char(from_x));
+                    san.push_str(&(game.oriented(from_y) + 1).to_string());
+                }
+            }
+        },
+        None if is_capture => san.push(file_char(from_x)),
+        None => ()
+    }
+    if is_capture {
+        san.push('x');
+    }
+    san.push(file_char(to_x));
+    san.push_str(&(game.oriented(to_y) + 1).to_string());
+    if let Some(letter) = m.promotion().and_then(piece_letter) {
+        san.push('=');
+        san.push(letter);
+    }
+
+    game.apply_legal_move(m);
+    if game.is_checkmate() {
+        san.push('#');
+    } else if !game.checkers().is_empty() {
+        san.push('+');
+    }
+    game.retract_move();
+    Some(san)
+}
@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+
+use crate::chess;
+use crate::graphics::{Color, Screen};
+
+/// Colors and glyphs used when drawing a board.
+#[derive(Clone, Copy)]
+pub struct BoardTheme {
+    pub light_square: Color,
+    pub dark_square: Color,
+    pub highlight: Color,
+    pub move_dot: Color,
+    pub check: Color,
+    pub glyphs: fn(&chess::Piece) -> char
+}
+
+impl Default for BoardTheme {
+    fn default() -> Self {
+        BoardTheme {
+            light_square: Color::new(196, 196, 196),
+            dark_square: Color::new(32, 32, 32),
+            highlight: Color::new(255, 255, 196),
+            move_dot: Color::new(128, 128, 196),
+            check: Color::new(196, 64, 64),
+            glyphs: piece_char
+        }
+    }
+}
+
+pub fn piece_char(piece: &chess::Piece) -> char {
+    match (piece.piece_type(), piece.piece_color()) {
+        (chess::PieceType::Pawn, chess::Color::White) => '♙',
+        (chess::PieceType::Pawn, chess::Color::Black) => '♟',
+        (chess::PieceType::Bishop, chess::Color::White) => '♗',
+        (chess::PieceType::Bishop, chess::Color::Black) => '♝',
+        (chess::PieceType::Knight, chess::Color::White) => '♘',
+        (chess::PieceType::Knight, chess::Color::Black) => '♞',
+        (chess::PieceType::Rook, chess::Color::White) => '♖',
+        (chess::PieceType::Rook, chess::Color::Black) => '♜',
+        (chess::PieceType::Queen, chess::Color::White) => '♕',
+        (chess::PieceType::Queen, chess::Color::Black) => '♛',
+        (chess::PieceType::King, chess::Color::White) => '♔',
+        (chess::PieceType::King, chess::Color::Black) => '♚',
+    }
+}
+
+/// Draws a piece glyph at (`x`, `y`) in a color readable on the cell's current background.
+pub fn draw_piece(piece: &chess::Piece, x: usize, y: usize, screen: &mut Screen, theme: &BoardTheme) {
+    let (bg, _, _) = screen.get_pixel(x, y);
+    let c = (theme.glyphs)(piece);
+    screen.set_pixel(x, y, None, Some(bg.readable_text_color()), Some(c));
+}
+
+/// Draws the board as the side to move sees it, with its top left square at (`x`, `y`).
+/// The king of the side to move is marked with the theme's check color while in check.
+pub fn draw_board(game: &chess::Game, x: usize, y: usize, screen: &mut Screen, theme: &BoardTheme) {
+    let in_check = !game.checkers().is_empty();
+    for dy in 0..chess::BOARD_SIZE {
+        for dx in 0..chess::BOARD_SIZE {
+            // a1 is a dark square whichever way the board is flipped
+            let mut bg = if (dx + game.oriented(dy)).is_multiple_of(2) {
+                theme.dark_square
+            } else {
+                theme.light_square
+            };
+            let piece = game.get_piece(dx, dy);
+            if in_check && piece.is_some_and(|piece| piece.piece_type() == chess::PieceType::King && piece.piece_color() == game.player_to_move()) {
+                bg = theme.check;
+            }
+            screen.set_pixel(x + dx, y + dy, Some(bg), None, None);
+
+            if let Some(piece) = piece {
+                draw_piece(&piece, x + dx, y + dy, screen, theme);
+            }
+        }
+    }
+}
+
+/// Marks the destination squares in `moves` with the theme's move color, for a board drawn at (`x`, `y`).
+pub fn draw_moves(moves: &HashSet<(usize, usize)>, x: usize, y: usize, screen: &mut Screen, theme: &BoardTheme) {
+    for (dx, dy) in moves {
+        screen.set_pixel(x + dx, y + dy, Some(theme.move_dot), None, None);
+    }
+}
+
+/// Produces `frames` screens with the piece on `from` sliding towards `to`, rounded to whole cells.
+/// `screen_provider` draws each frame's backdrop, and the board's top left square is at `board`.
+/// The piece is drawn with the theme's glyphs.
+pub fn animate_move(screen_provider: impl Fn() -> Screen, game: &chess::Game, board: (usize, usize), from: (usize, usize), to: (usize, usize), frames: usize, theme: &BoardTheme) -> Vec<Screen> {
+    let (board_x, board_y) = board;
+    let mut result = Vec::with_capacity(frames);
+    let piece = match game.get_piece(from.0, from.1) {
+        Some(piece) => piece,
+        None => return result
+    };
+    let c = (theme.glyphs)(&piece);
+
+    for i in 0..frames {
+        let mut screen = screen_provider();
+        let t = if frames > 1 { i as f32 / (frames - 1) as f32 } else { 1.0 };
+
+        let x = from.0 as f32 + (to.0 as f32 - from.0 as f32) * t;
+        let y = from.1 as f32 + (to.1 as f32 - from.1 as f32) * t;
+        let x = board_x + x.round() as usize;
+        let y = board_y + y.round() as usize;
+
+        // fade the glyph between the colors readable on the start and end squares
+        let (from_bg, _, _) = screen.get_pixel(board_x + from.0, board_y + from.1);
+        let (to_bg, _, _) = screen.get_pixel(board_x + to.0, board_y + to.1);
+        let fg = from_bg.readable_text_color().lerp(to_bg.readable_text_color(), t);
+
+        screen.set_pixel(board_x + from.0, board_y + from.1, None, None, Some(' '));
+        screen.set_pixel(x, y, None, Some(fg), Some(c));
+        result.push(screen);
+    }
+    result
+}
+
+impl chess::Game {
+    /// A plain text diagram of the board with unicode glyphs, '.' for empty squares and rank and
+    /// file labels, with `bottom`'s pieces at the bottom. Needs no Screen or terminal colors.
+    pub fn to_unicode_string(&self, bottom: chess::Color) -> String {
+        let ranks: Vec<usize> = match bottom {
+            chess::Color::White => (0..chess::BOARD_SIZE).rev().collect(),
+            chess::Color::Black => (0..chess::BOARD_SIZE).collect()
+        };
+        let files: Vec<usize> = match bottom {
+            chess::Color::White => (0..chess::BOARD_SIZE).collect(),
+            chess::Color::Black => (0..chess::BOARD_SIZE).rev().collect()
+        };
+
+        let mut text = String::new();
+        for &rank in &ranks {
+            text.push_str(&(rank + 1).to_string());
+            for &x in &files {
+                text.push(' ');
+                text.push(self.get_piece(x, self.oriented(rank)).map_or('.', |piece| piece_char(&piece)));
+            }
+            text.push('\n');
+        }
+        text.push(' ');
+        for &x in &files {
+            text.push(' ');
+            text.push((b'a' + x as u8) as char);
+        }
+        text.push('\n');
+        text
+    }
+}
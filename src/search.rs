@@ -0,0 +1,393 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::chess::{Color, Game, Move, PieceType};
+use crate::eval::EvalWeights;
+
+const INFINITY: i32 = 1_000_000;
+const MATE: i32 = 100_000;
+const MAX_DEPTH: u32 = 64;
+const MAX_EXTENSIONS: u32 = 8;
+const NULL_MOVE_REDUCTION: u32 = 2;
+const MAX_HINT_DEPTH: u32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TTEntry {
+    depth: u32,
+    score: i32,
+    bound: Bound,
+    best_move: Option<Move>
+}
+
+/// State kept between searches, most importantly the transposition table.
+#[derive(Default)]
+pub struct SearchContext {
+    table: HashMap<u64, TTEntry>,
+    mobility: bool,
+    weights: EvalWeights,
+    null_move_pruning: bool,
+    deadline: Option<Instant>,
+    stopped: bool
+}
+
+// mate scores are stored relative to the node so they stay valid when the position is reached at another ply
+fn score_to_table(score: i32, ply: u32) -> i32 {
+    if score > MATE - 1000 {
+        score + ply as i32
+    } else if score < -MATE + 1000 {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: u32) -> i32 {
+    if score > MATE - 1000 {
+        score - ply as i32
+    } else if score < -MATE + 1000 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+fn evaluate_relative(game: &Game, mobility: bool, weights: &EvalWeights) -> i32 {
+    let mut score = game.evaluate_positional() + game.evaluate_rook_placement_with(weights) + game.evaluate_bishop_pair_with(weights);
+    if mobility {
+        score += game.evaluate_mobility();
+    }
+    match game.player_to_move() {
+        Color::White => score,
+        Color::Black => -score
+    }
+}
+
+impl SearchContext {
+    pub fn new() -> Self {
+        SearchContext {
+            table: HashMap::new(),
+            mobility: false,
+            weights: EvalWeights::default(),
+            null_move_pruning: false,
+            deadline: None,
+            stopped: false
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    /// Whether leaf positions also score evaluate_mobility. Off by default since it's slower
+    /// to compute. Clears the table, as the stored scores no longer match.
+    pub fn set_mobility(&mut self, enabled: bool) {
+        self.mobility = enabled;
+        self.clear();
+    }
+
+    /// The bonuses leaf positions score for rook placement and the bishop pair. Clears the table,
+    /// as the stored scores no longer match.
+    pub fn set_weights(&mut self, weights: EvalWeights) {
+        self.weights = weights;
+        self.clear();
+    }
+
+    /// Whether the search skips lines where the side to move is doing so well that even passing
+    /// with a null move, searched a little shallower, keeps the score above beta. Makes deeper
+    /// searches much faster but can miss zugzwangs, so it's off by default.
+    pub fn set_null_move_pruning(&mut self, enabled: bool) {
+        self.null_move_pruning = enabled;
+        self.clear();
+    }
+
+    /// Returns the opening book move if there is one, and otherwise searches `depth` plies
+    /// with alpha-beta for the best move for the side to move.
+    pub fn best_move(&mut self, game: &mut Game, depth: u32) -> Option<Move> {
+        if let Some(m) = game.book_move() {
+            return Some(m);
+        }
+
+        self.deadline = None;
+        self.stopped = false;
+        self.search_root(game, depth)
+    }
+
+    /// Like best_move, but deepens one ply at a time until `max_millis` have passed and returns the
+    /// best move of the deepest search that finished. An unfinished search is thrown away.
+    pub fn best_move_timed(&mut self, game: &mut Game, max_millis: u64) -> Option<Move> {
+        if let Some(m) = game.book_move() {
+            return Some(m);
+        }
+
+        self.deadline = Some(Instant::now() + Duration::from_millis(max_millis));
+        self.stopped = false;
+        // fall back to any legal move in case not even the first iteration finishes
+        let mut best = game.get_all_legal_moves().first().copied();
+        for depth in 1..=MAX_DEPTH {
+            match self.search_root(game, depth) {
+                Some(m) if !self.stopped => best = Some(m),
+                _ => break
+            }
+        }
+        self.deadline = None;
+        best
+    }
+
+    /// Like best_move, but also returns the principal variation, the line of play the search expects
+    /// starting with the best move. It's read back from the transposition table and can be shorter
+    /// than `depth` where entries were overwritten.
+    pub fn best_move_pv(&mut self, game: &mut Game, depth: u32) -> (Option<Move>, Vec<Move>) {
+        let best = self.best_move(game, depth);
+        if game.book_move().is_some() {
+            return (best, best.into_iter().collect());
+        }
+        (best, self.principal_variation(game, depth))
+    }
+
+    fn principal_variation(&self, game: &mut Game, depth: u32) -> Vec<Move> {
+        let mut pv = Vec::new();
+        let mut seen = HashSet::new();
+        while pv.len() < depth as usize && seen.insert(game.zobrist_hash()) {
+            let m = match self.table.get(&game.zobrist_hash()).and_then(|entry| entry.best_move) {
+                Some(m) => m,
+                None => break
+            };
+            // a hash collision could point at a move that doesn't exist here
+            if !game.get_all_legal_moves().contains(&m) {
+                break;
+            }
+            game.apply_legal_move(m);
+            pv.push(m);
+        }
+        for _ in 0..pv.len() {
+            game.retract_move();
+        }
+        pv
+    }
+
+    fn search_root(&mut self, game: &mut Game, depth: u32) -> Option<Move> {
+        let mut moves = game.get_ordered_moves();
+        self.order_moves(game.zobrist_hash(), &mut moves);
+
+        let mut best: Option<(Move, i32)> = None;
+        let mut alpha = -INFINITY;
+        for m in moves {
+            game.apply_legal_move(m);
+            let score = -self.negamax(game, depth.saturating_sub(1), 1, 0, -INFINITY, -alpha);
+            game.retract_move();
+            if self.stopped {
+                return None;
+            }
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((m, score));
+                alpha = alpha.max(score);
+            }
+        }
+
+        if let Some((m, score)) = best {
+            self.table.insert(game.zobrist_hash(), TTEntry {
+                depth,
+                score: score_to_table(score, 0),
+                bound: Bound::Exact,
+                best_move: Some(m)
+            });
+        }
+        best.map(|(m, _)| m)
+    }
+
+    fn out_of_time(&mut self) -> bool {
+        if !self.stopped {
+            if let Some(deadline) = self.deadline {
+                self.stopped = Instant::now() >= deadline;
+            }
+        }
+        self.stopped
+    }
+
+    fn order_moves(&self, hash: u64, moves: &mut [Move]) {
+        if let Some(tt_move) = self.table.get(&hash).and_then(|entry| entry.best_move) {
+            if let Some(i) = moves.iter().position(|&m| m == tt_move) {
+                moves[..=i].rotate_right(1);
+            }
+        }
+    }
+
+    fn negamax(&mut self, game: &mut Game, mut depth: u32, ply: u32, mut extensions: u32, mut alpha: i32, mut beta: i32) -> i32 {
+        // the score of an aborted search is never used, so any value does
+        if self.out_of_time() {
+            return 0;
+        }
+
+        // search one ply deeper when in check so mates just past the horizon aren't missed,
+        // a limited number of times along a line so perpetual checks still come to an end
+        let in_check = !game.checkers().is_empty();
+        if in_check && extensions < MAX_EXTENSIONS {
+            depth += 1;
+            extensions += 1;
+        }
+        let original_alpha = alpha;
+        let hash = game.zobrist_hash();
+
+        if let Some(entry) = self.table.get(&hash) {
+            if entry.depth >= depth {
+                let score = score_from_table(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower => alpha = alpha.max(score),
+                    Bound::Upper => beta = beta.min(score)
+                }
+                if alpha >= beta {
+                    return score;
+                }
+            }
+        }
+
+        if depth == 0 {
+            return self.quiescence(game, alpha, beta);
+        }
+
+        // never two null moves in a row, or the search would just pass back and forth
+        if self.null_move_pruning && !in_check && depth > NULL_MOVE_REDUCTION && !game.after_null_move() && has_pieces(game) {
+            game.make_null_move();
+            let score = -self.negamax(game, depth - 1 - NULL_MOVE_REDUCTION, ply + 1, extensions, -beta, -beta + 1);
+            game.unmake_null_move();
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+        }
+
+        let mut moves = game.get_ordered_moves();
+        if moves.is_empty() {
+            if !in_check {
+                return 0;
+            }
+            return -MATE + ply as i32;
+        }
+        self.order_moves(hash, &mut moves);
+
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        for m in moves {
+            game.apply_legal_move(m);
+            let score = -self.negamax(game, depth - 1, ply + 1, extensions, -beta, -alpha);
+            game.retract_move();
+            if self.stopped {
+                return 0;
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = Some(m);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.insert(hash, TTEntry {
+            depth,
+            score: score_to_table(best_score, ply),
+            bound,
+            best_move
+        });
+        best_score
+    }
+}
+
+impl SearchContext {
+    /// Keeps searching captures past the depth limit until the position is quiet, so the
+    /// search doesn't stop in the middle of an exchange. Standing pat is always allowed.
+    fn quiescence(&mut self, game: &mut Game, mut alpha: i32, beta: i32) -> i32 {
+        if self.out_of_time() {
+            return 0;
+        }
+        let stand_pat = evaluate_relative(game, self.mobility, &self.weights);
+        if stand_pat >= beta {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut captures = game.get_all_legal_captures();
+        captures.sort_by_key(|&m| mvv_lva(game, m));
+        for m in captures {
+            game.apply_legal_move(m);
+            let score = -self.quiescence(game, -beta, -alpha);
+            game.retract_move();
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+}
+
+// with only pawns left, passing may well be better than any move, so a null move proves nothing
+fn has_pieces(game: &Game) -> bool {
+    [PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen].into_iter()
+        .any(|piece_type| game.count(piece_type, game.player_to_move()) > 0)
+}
+
+// most valuable victim first, and among those the least valuable attacker, as a key for sorting
+// in ascending order. Quiet moves sort after every capture.
+fn mvv_lva(game: &Game, m: Move) -> (i32, i32) {
+    let (from_x, from_y) = m.from();
+    let (to_x, to_y) = m.to();
+    match (game.get_piece(to_x, to_y), game.get_piece(from_x, from_y)) {
+        (Some(victim), Some(attacker)) => (-victim.piece_type().value(), attacker.piece_type().value()),
+        _ => (1, 0)
+    }
+}
+
+impl Game {
+    /// All legal moves for the side to move in the order the search tries them: captures first,
+    /// most valuable victim and then least valuable attacker first, followed by the quiet moves
+    /// in the order of get_all_legal_moves.
+    pub fn get_ordered_moves(&mut self) -> Vec<Move> {
+        let mut moves = self.get_all_legal_moves();
+        moves.sort_by_key(|&m| mvv_lva(self, m));
+        moves
+    }
+
+    /// Searches `depth` plies with a fresh SearchContext and returns the best move for the side to move.
+    pub fn best_move(&mut self, depth: u32) -> Option<Move> {
+        SearchContext::new().best_move(self, depth)
+    }
+
+    /// Searches `depth` plies with a fresh SearchContext and returns the best move along with
+    /// the line the search expects to follow it.
+    pub fn best_move_pv(&mut self, depth: u32) -> (Option<Move>, Vec<Move>) {
+        SearchContext::new().best_move_pv(self, depth)
+    }
+
+    /// Suggests a move for the side to move, whichever color that is, for example for a hint button.
+    /// Searches like best_move, but never deeper than MAX_HINT_DEPTH plies so it answers quickly.
+    pub fn hint(&mut self, depth: u32) -> Option<Move> {
+        self.best_move(depth.min(MAX_HINT_DEPTH))
+    }
+
+    /// Searches with iterative deepening for at most `max_millis` with a fresh SearchContext.
+    pub fn best_move_timed(&mut self, max_millis: u64) -> Option<Move> {
+        SearchContext::new().best_move_timed(self, max_millis)
+    }
+}
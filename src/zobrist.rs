@@ -0,0 +1,57 @@
+use crate::chess::{Color, Game, PieceType, BOARD_SIZE};
+
+const PIECE_KEYS: usize = 12 * 64;
+const SIDE_KEY: usize = PIECE_KEYS;
+
+const fn generate_keys() -> [u64; PIECE_KEYS + 1] {
+    // splitmix64, so the keys are fixed at compile time
+    let mut keys = [0; PIECE_KEYS + 1];
+    let mut state: u64 = 0x2545F4914F6CDD1D;
+    let mut i = 0;
+    while i < keys.len() {
+        state = state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+const KEYS: [u64; PIECE_KEYS + 1] = generate_keys();
+
+fn piece_index(piece_type: PieceType, piece_color: Color) -> usize {
+    let type_index = match piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Bishop => 1,
+        PieceType::Knight => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+    };
+    match piece_color {
+        Color::White => type_index,
+        Color::Black => type_index + 6
+    }
+}
+
+impl Game {
+    /// Zobrist hash of the piece placement and side to move. Squares are hashed by rank rather
+    /// than board row, so the hash doesn't depend on which way the board is currently flipped.
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+        for y in 0..BOARD_SIZE {
+            for x in 0..BOARD_SIZE {
+                if let Some(piece) = self.get_piece(x, y) {
+                    let square = self.oriented(y) * BOARD_SIZE + x;
+                    hash ^= KEYS[piece_index(piece.piece_type(), piece.piece_color()) * 64 + square];
+                }
+            }
+        }
+        if self.player_to_move() == Color::Black {
+            hash ^= KEYS[SIDE_KEY];
+        }
+        hash
+    }
+}